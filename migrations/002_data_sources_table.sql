@@ -0,0 +1,14 @@
+-- Add migration script for data sources table
+CREATE TABLE IF NOT EXISTS data_sources (
+    id VARCHAR(64) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    type VARCHAR(32) NOT NULL,
+    connection_string TEXT NOT NULL,
+    options JSONB NOT NULL DEFAULT '{}'::jsonb,
+    schema TEXT,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_data_sources_name ON data_sources (name);
@@ -0,0 +1,4 @@
+-- Track the outcome of the latest connection test of each data source
+ALTER TABLE data_sources ADD COLUMN IF NOT EXISTS status VARCHAR(16) NOT NULL DEFAULT 'unknown';
+ALTER TABLE data_sources ADD COLUMN IF NOT EXISTS last_checked_at TIMESTAMP;
+ALTER TABLE data_sources ADD COLUMN IF NOT EXISTS last_error TEXT;
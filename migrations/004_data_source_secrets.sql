@@ -0,0 +1,2 @@
+-- Credentials of each data source, encrypted with the configured master key
+ALTER TABLE data_sources ADD COLUMN IF NOT EXISTS secrets TEXT;
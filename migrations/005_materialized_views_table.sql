@@ -0,0 +1,16 @@
+-- Materialized views: a query whose result is stored as Parquet and refreshed
+CREATE TABLE IF NOT EXISTS materialized_views (
+    id VARCHAR(64) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    sql TEXT NOT NULL,
+    schedule VARCHAR(255),
+    location TEXT,
+    last_refreshed_at TIMESTAMP,
+    row_count BIGINT,
+    refresh_duration_ms BIGINT,
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_materialized_views_name ON materialized_views (name);
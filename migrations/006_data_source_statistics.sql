@@ -0,0 +1,7 @@
+-- Statistics from the latest analysis of each data source
+CREATE TABLE IF NOT EXISTS data_source_statistics (
+    data_source_id VARCHAR(64) PRIMARY KEY,
+    tables JSONB NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    analyzed_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Columns declared by the user, kept apart from the schema found at registration.
+-- Only memory tables could not have been inferred, so only theirs carry over.
+ALTER TABLE data_sources ADD COLUMN IF NOT EXISTS columns TEXT;
+UPDATE data_sources SET columns = schema WHERE type = 'Memory' AND columns IS NULL;
@@ -0,0 +1,485 @@
+use super::catalog::SourceTables;
+use super::data_source::DataSourceConfig;
+use super::sql_pushdown::{
+    build_select, filters_pushdown, AnsiDialect, MySqlDialect, PostgresDialect, RemoteColumn, SqlDialect,
+    SqliteDialect,
+};
+use crate::utils::{AppError, AppResult};
+use adbc::driver_manager::{ManagedConnection, ManagedDatabase, ManagedDriver};
+use adbc::options::{AdbcVersion, ObjectDepth, OptionDatabase, OptionValue};
+use adbc::{Connection, Database, Driver, Statement};
+use arrow::array::{Array, ListArray, StringArray, StructArray};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use async_trait::async_trait;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::SessionState;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::streaming::{PartitionStream, StreamingTableExec};
+use datafusion::physical_plan::ExecutionPlan;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Options forwarded verbatim to the driver when creating the database, with the prefix removed.
+const DRIVER_OPTION_PREFIX: &str = "adbc.";
+
+fn adbc_error(e: adbc::error::Error) -> AppError {
+    AppError::InternalError(format!("ADBC error: {}", e))
+}
+
+/// Whether `driver` names a shared library file rather than a driver to look up by name.
+pub fn is_driver_path(driver: &str) -> bool {
+    driver.contains('/')
+        || driver.contains('\\')
+        || [".so", ".dylib", ".dll"].iter().any(|ext| driver.ends_with(ext))
+}
+
+/// Where an allowed driver is loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriverLocation {
+    /// Looked up by the driver manager.
+    Name(String),
+    /// A shared library in the driver directory.
+    Path(PathBuf),
+}
+
+/// The ADBC drivers data sources may use. Loading a driver runs its code in
+/// this process, so only libraries in the configured directory and drivers
+/// named in the allowlist are accepted; with neither, ADBC is disabled.
+#[derive(Debug, Clone, Default)]
+pub struct AdbcDrivers {
+    dir: Option<PathBuf>,
+    names: Vec<String>,
+}
+
+impl AdbcDrivers {
+    pub fn new(dir: Option<impl Into<PathBuf>>, names: Vec<String>) -> Self {
+        AdbcDrivers {
+            dir: dir.map(Into::into),
+            names,
+        }
+    }
+
+    /// Resolves `options.driver`. A library is given by its file name in the
+    /// driver directory, and must still be in it once symlinks are followed.
+    pub fn resolve(&self, driver: &str) -> AppResult<DriverLocation> {
+        let refused = || AppError::ValidationError(format!("ADBC driver {} is not allowed", driver));
+        if !is_driver_path(driver) {
+            return match self.names.iter().any(|name| name == driver) {
+                true => Ok(DriverLocation::Name(driver.to_string())),
+                false => Err(refused()),
+            };
+        }
+
+        let dir = self.dir.as_ref().ok_or_else(refused)?;
+        let file_name = Path::new(driver).file_name().filter(|name| *name == driver).ok_or_else(refused)?;
+        let dir = dir.canonicalize().map_err(|_| refused())?;
+        let path = dir.join(file_name).canonicalize().map_err(|_| refused())?;
+        if !path.starts_with(&dir) {
+            return Err(refused());
+        }
+        Ok(DriverLocation::Path(path))
+    }
+}
+
+/// Picks the SQL dialect used for pushdown from the driver name.
+pub fn dialect_for_driver(driver: &str) -> Arc<dyn SqlDialect> {
+    let driver = driver.to_lowercase();
+    if driver.contains("postgres") {
+        Arc::new(PostgresDialect)
+    } else if driver.contains("mysql") {
+        Arc::new(MySqlDialect)
+    } else if driver.contains("sqlite") {
+        Arc::new(SqliteDialect)
+    } else {
+        Arc::new(AnsiDialect)
+    }
+}
+
+/// A table read through an ADBC driver. Each scan opens its own connection
+/// and runs the pushed-down SQL as an ADBC statement on a blocking thread,
+/// forwarding the Arrow batches as the driver produces them.
+pub struct AdbcTable {
+    database: Arc<Mutex<ManagedDatabase>>,
+    dialect: Arc<dyn SqlDialect>,
+    table: String,
+    columns: Vec<RemoteColumn>,
+    schema: SchemaRef,
+}
+
+impl AdbcTable {
+    fn try_new(
+        database: Arc<Mutex<ManagedDatabase>>,
+        connection: &ManagedConnection,
+        dialect: Arc<dyn SqlDialect>,
+        catalog: Option<&str>,
+        db_schema: Option<&str>,
+        table_name: &str,
+    ) -> AppResult<Self> {
+        let schema = connection
+            .get_table_schema(catalog, db_schema, table_name)
+            .map_err(adbc_error)?;
+
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| RemoteColumn {
+                name: f.name().clone(),
+                data_type: f.data_type().clone(),
+                nullable: f.is_nullable(),
+                select_expr: dialect.quote_identifier(f.name()),
+            })
+            .collect();
+
+        let table = [catalog, db_schema, Some(table_name)]
+            .iter()
+            .flatten()
+            .map(|part| dialect.quote_identifier(part))
+            .collect::<Vec<_>>()
+            .join(".");
+
+        Ok(AdbcTable {
+            database,
+            dialect,
+            table,
+            columns,
+            schema: Arc::new(schema),
+        })
+    }
+}
+
+#[async_trait]
+impl TableProvider for AdbcTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters_pushdown(self.dialect.as_ref(), &self.schema, filters))
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let columns: Vec<&RemoteColumn> = match projection {
+            Some(indices) => indices.iter().map(|i| &self.columns[*i]).collect(),
+            None => self.columns.iter().collect(),
+        };
+        let projected_schema = match projection {
+            Some(indices) => Arc::new(self.schema.project(indices)?),
+            None => self.schema.clone(),
+        };
+
+        let sql = build_select(self.dialect.as_ref(), &self.table, &columns, filters, limit);
+        tracing::debug!("ADBC pushdown query: {}", sql);
+
+        let partition = AdbcPartition {
+            database: self.database.clone(),
+            sql,
+            schema: projected_schema.clone(),
+        };
+        Ok(Arc::new(StreamingTableExec::try_new(
+            projected_schema,
+            vec![Arc::new(partition)],
+            None,
+            vec![],
+            false,
+            None,
+        )?))
+    }
+}
+
+struct AdbcPartition {
+    database: Arc<Mutex<ManagedDatabase>>,
+    sql: String,
+    schema: SchemaRef,
+}
+
+impl PartitionStream for AdbcPartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let mut builder = RecordBatchReceiverStream::builder(self.schema.clone(), 2);
+        let tx = builder.tx();
+        let database = self.database.clone();
+        let sql = self.sql.clone();
+        let schema = self.schema.clone();
+
+        builder.spawn_blocking(move || {
+            let to_df = |e: adbc::error::Error| DataFusionError::External(Box::new(e));
+
+            let mut connection = database.lock().unwrap().new_connection().map_err(to_df)?;
+            let mut statement = connection.new_statement().map_err(to_df)?;
+            statement.set_sql_query(&sql).map_err(to_df)?;
+            let reader = statement.execute().map_err(to_df)?;
+
+            for batch in reader {
+                let batch = conform_batch(batch?, &schema)?;
+                // The receiver is gone once the query stops early, e.g. on LIMIT
+                if tx.blocking_send(Ok(batch)).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        builder.build()
+    }
+}
+
+/// Drivers may report slightly different types at execution time than in
+/// `get_table_schema` (e.g. nullability or integer width); cast to the declared schema.
+fn conform_batch(batch: RecordBatch, schema: &SchemaRef) -> DataFusionResult<RecordBatch> {
+    if batch.schema().fields().len() != schema.fields().len() {
+        // `SELECT 1` for projections without columns
+        let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+        return Ok(RecordBatch::try_new_with_options(schema.clone(), vec![], &options)?);
+    }
+
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| {
+            if column.data_type() == field.data_type() {
+                Ok(column.clone())
+            } else {
+                arrow::compute::cast(column, field.data_type())
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Opens a data source through an ADBC driver.
+///
+/// `options["driver"]` is a driver name (resolved by the driver manager) or the
+/// file name of the driver's shared library, and must be allowed by `drivers`;
+/// `options["entrypoint"]` overrides the init symbol. `connection_string` is passed as the database `uri`, and any
+/// `adbc.<key>` option is forwarded to the driver as `<key>`. Tables are
+/// selected with `table`, `schema` and `catalog` like the native adapters.
+pub async fn load_tables(config: &DataSourceConfig, drivers: &AdbcDrivers) -> AppResult<SourceTables> {
+    let location = driver_location(config, drivers)?;
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || load_tables_blocking(&config, &location))
+        .await
+        .map_err(|e| AppError::InternalError(format!("ADBC loader panicked: {}", e)))?
+}
+
+/// Checks that the driver loads and opens a connection to the database.
+pub async fn ping(config: &DataSourceConfig, drivers: &AdbcDrivers) -> AppResult<()> {
+    let location = driver_location(config, drivers)?;
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || connect_blocking(&config, &location).map(|_| ()))
+        .await
+        .map_err(|e| AppError::InternalError(format!("ADBC connection check panicked: {}", e)))?
+}
+
+fn driver_location(config: &DataSourceConfig, drivers: &AdbcDrivers) -> AppResult<DriverLocation> {
+    let driver_name = config
+        .options
+        .get("driver")
+        .ok_or_else(|| AppError::ValidationError("ADBC data sources require options.driver".to_string()))?;
+    drivers.resolve(driver_name)
+}
+
+/// Opens the database and returns it with the driver name it was loaded by.
+fn open_database(config: &DataSourceConfig, location: &DriverLocation) -> AppResult<(ManagedDatabase, String)> {
+    let entrypoint = config.options.get("entrypoint").map(|e| e.as_bytes());
+
+    let (driver, driver_name) = match location {
+        DriverLocation::Path(path) => (
+            ManagedDriver::load_dynamic_from_filename(path, entrypoint, AdbcVersion::V110),
+            path.to_string_lossy().into_owned(),
+        ),
+        DriverLocation::Name(name) => (
+            ManagedDriver::load_dynamic_from_name(name, entrypoint, AdbcVersion::V110),
+            name.clone(),
+        ),
+    };
+    let mut driver = driver.map_err(adbc_error)?;
+
+    let mut database_options = vec![(
+        OptionDatabase::Uri,
+        OptionValue::String(config.connection_string.clone()),
+    )];
+    for (key, value) in &config.options {
+        if let Some(driver_key) = key.strip_prefix(DRIVER_OPTION_PREFIX) {
+            database_options.push((
+                OptionDatabase::Other(driver_key.to_string()),
+                OptionValue::String(value.clone()),
+            ));
+        }
+    }
+
+    let database = driver.new_database_with_opts(database_options).map_err(adbc_error)?;
+    Ok((database, driver_name))
+}
+
+fn connect_blocking(config: &DataSourceConfig, location: &DriverLocation) -> AppResult<ManagedConnection> {
+    let (mut database, _) = open_database(config, location)?;
+    database.new_connection().map_err(adbc_error)
+}
+
+fn load_tables_blocking(config: &DataSourceConfig, location: &DriverLocation) -> AppResult<SourceTables> {
+    let (mut database, driver_name) = open_database(config, location)?;
+    // Only used to read the catalog; scans open their own connections
+    let connection = database.new_connection().map_err(adbc_error)?;
+    let database = Arc::new(Mutex::new(database));
+    let dialect = dialect_for_driver(&driver_name);
+
+    let catalog = config.options.get("catalog").map(String::as_str);
+    let db_schema = config.options.get("schema").map(String::as_str);
+
+    if let Some(table_name) = config.options.get("table") {
+        let table = AdbcTable::try_new(database, &connection, dialect, catalog, db_schema, table_name)?;
+        return Ok(SourceTables::Table(Arc::new(table)));
+    }
+
+    let tables = list_tables(&connection, catalog, db_schema)?;
+    let mut by_schema: BTreeMap<String, Vec<(String, Arc<dyn TableProvider>)>> = BTreeMap::new();
+    for (schema_name, table_name) in tables {
+        let remote_schema = if schema_name.is_empty() { None } else { Some(schema_name.as_str()) };
+        let table = AdbcTable::try_new(
+            database.clone(),
+            &connection,
+            dialect.clone(),
+            catalog,
+            remote_schema,
+            &table_name,
+        )?;
+        by_schema
+            .entry(schema_name.clone())
+            .or_default()
+            .push((table_name, Arc::new(table)));
+    }
+
+    if db_schema.is_some() || by_schema.len() <= 1 {
+        let tables = by_schema.into_values().next().unwrap_or_default();
+        return Ok(SourceTables::Schema(tables));
+    }
+    Ok(SourceTables::Catalog(by_schema.into_iter().collect()))
+}
+
+fn list_tables(
+    connection: &ManagedConnection,
+    catalog: Option<&str>,
+    db_schema: Option<&str>,
+) -> AppResult<Vec<(String, String)>> {
+    let reader = connection
+        .get_objects(ObjectDepth::Tables, catalog, db_schema, None, None, None)
+        .map_err(adbc_error)?;
+
+    let mut tables = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| AppError::InternalError(format!("ADBC error: {}", e)))?;
+        collect_tables(&batch, &mut tables).ok_or_else(|| {
+            AppError::InternalError("Unexpected GetObjects result layout from ADBC driver".to_string())
+        })?;
+    }
+    Ok(tables)
+}
+
+/// Walks the nested `GetObjects` layout:
+/// `catalog_db_schemas: list<struct<db_schema_name, db_schema_tables: list<struct<table_name, ..>>>>`.
+fn collect_tables(batch: &RecordBatch, out: &mut Vec<(String, String)>) -> Option<()> {
+    let schemas = batch
+        .column_by_name("catalog_db_schemas")?
+        .as_any()
+        .downcast_ref::<ListArray>()?;
+
+    for i in 0..schemas.len() {
+        if schemas.is_null(i) {
+            continue;
+        }
+        let schema_list = schemas.value(i);
+        let schema_structs = schema_list.as_any().downcast_ref::<StructArray>()?;
+        let schema_names = schema_structs
+            .column_by_name("db_schema_name")?
+            .as_any()
+            .downcast_ref::<StringArray>()?;
+        let schema_tables = schema_structs
+            .column_by_name("db_schema_tables")?
+            .as_any()
+            .downcast_ref::<ListArray>()?;
+
+        for j in 0..schema_structs.len() {
+            if schema_tables.is_null(j) {
+                continue;
+            }
+            let schema_name = if schema_names.is_null(j) { "" } else { schema_names.value(j) };
+            let table_list = schema_tables.value(j);
+            let table_structs = table_list.as_any().downcast_ref::<StructArray>()?;
+            let table_names = table_structs
+                .column_by_name("table_name")?
+                .as_any()
+                .downcast_ref::<StringArray>()?;
+            for k in 0..table_names.len() {
+                out.push((schema_name.to_string(), table_names.value(k).to_string()));
+            }
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_driver_path() {
+        assert!(is_driver_path("/usr/lib/libadbc_driver_snowflake.so"));
+        assert!(is_driver_path("adbc_driver_duckdb.dylib"));
+        assert!(!is_driver_path("adbc_driver_postgresql"));
+    }
+
+    #[test]
+    fn test_allowed_drivers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("libadbc_driver_sqlite.so"), b"").unwrap();
+
+        let drivers = AdbcDrivers::new(Some(dir), vec!["adbc_driver_postgresql".to_string()]);
+        assert_eq!(
+            drivers.resolve("adbc_driver_postgresql").unwrap(),
+            DriverLocation::Name("adbc_driver_postgresql".to_string())
+        );
+        assert!(matches!(drivers.resolve("libadbc_driver_sqlite.so"), Ok(DriverLocation::Path(_))));
+        for driver in [
+            "adbc_driver_snowflake",
+            "libadbc_driver_duckdb.so",
+            "/tmp/evil.so",
+            "../libadbc_driver_sqlite.so",
+        ] {
+            assert!(drivers.resolve(driver).is_err(), "{}", driver);
+        }
+        assert!(AdbcDrivers::default().resolve("adbc_driver_postgresql").is_err());
+    }
+
+    #[test]
+    fn test_dialect_for_driver() {
+        assert_eq!(dialect_for_driver("adbc_driver_mysql").quote_identifier("t"), "`t`");
+        assert_eq!(dialect_for_driver("adbc_driver_snowflake").quote_identifier("t"), "\"t\"");
+    }
+}
@@ -0,0 +1,694 @@
+use crate::config::DataFusionConfig;
+use crate::utils::{AppError, AppResult};
+use async_trait::async_trait;
+use datafusion::catalog::schema::{MemorySchemaProvider, SchemaProvider};
+use datafusion::catalog::{CatalogProvider, CatalogProviderList, MemoryCatalogProvider};
+use datafusion::common::tree_node::{TreeNode, TreeNodeRecursion};
+use datafusion::datasource::function::TableFunctionImpl;
+use datafusion::datasource::{TableProvider, ViewTable};
+use datafusion::error::Result as DataFusionResult;
+use datafusion::execution::context::{SessionConfig, SessionContext, SessionState};
+use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
+use datafusion::logical_expr::LogicalPlan;
+use datafusion::sql::ResolvedTableReference;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock, Weak};
+
+pub const DEFAULT_CATALOG: &str = "datafusion";
+pub const DEFAULT_SCHEMA: &str = "public";
+
+/// Names a data source may not take, so it cannot shadow built-in namespaces.
+const RESERVED_NAMES: &[&str] = &[DEFAULT_CATALOG, DEFAULT_SCHEMA, "information_schema"];
+const MAX_NAME_LENGTH: usize = 63;
+
+/// Checks that a data source name works unquoted in SQL. DataFusion lowercases
+/// unquoted identifiers, so only lowercase names resolve as written.
+pub fn validate_source_name(name: &str) -> AppResult<()> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some('a'..='z' | '_'))
+        && chars.all(|c| matches!(c, 'a'..='z' | '0'..='9' | '_'))
+        && name.len() <= MAX_NAME_LENGTH;
+    if !valid {
+        return Err(AppError::ValidationError(format!(
+            "Invalid data source name {:?}: use at most {} lowercase letters, digits and underscores, not starting with a digit",
+            name, MAX_NAME_LENGTH
+        )));
+    }
+    if RESERVED_NAMES.contains(&name) {
+        return Err(AppError::ValidationError(format!("Data source name {} is reserved", name)));
+    }
+    Ok(())
+}
+
+/// Tables produced by a data source, shaped by how much of the source system it covers.
+pub enum SourceTables {
+    /// A single table, registered in a schema named after the data source
+    /// under the same name and also found unqualified.
+    Table(Arc<dyn TableProvider>),
+    /// Several tables, registered in a schema named after the data source.
+    Schema(Vec<(String, Arc<dyn TableProvider>)>),
+    /// Several schemas, registered in a catalog named after the data source.
+    Catalog(Vec<(String, Vec<(String, Arc<dyn TableProvider>)>)>),
+}
+
+impl SourceTables {
+    /// Every table with its path below the data source: empty for a single
+    /// table, `[table]` in a schema and `[schema, table]` in a catalog.
+    pub fn tables(&self) -> Vec<(Vec<String>, Arc<dyn TableProvider>)> {
+        match self {
+            SourceTables::Table(table) => vec![(vec![], table.clone())],
+            SourceTables::Schema(tables) => tables
+                .iter()
+                .map(|(name, table)| (vec![name.clone()], table.clone()))
+                .collect(),
+            SourceTables::Catalog(schemas) => schemas
+                .iter()
+                .flat_map(|(schema, tables)| {
+                    tables
+                        .iter()
+                        .map(move |(name, table)| (vec![schema.clone(), name.clone()], table.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    /// Replaces every table with `f(path, table)`, keeping the shape.
+    pub fn map(self, f: impl Fn(&[String], Arc<dyn TableProvider>) -> Arc<dyn TableProvider>) -> SourceTables {
+        match self {
+            SourceTables::Table(table) => SourceTables::Table(f(&[], table)),
+            SourceTables::Schema(tables) => SourceTables::Schema(
+                tables
+                    .into_iter()
+                    .map(|(name, table)| {
+                        let table = f(std::slice::from_ref(&name), table);
+                        (name, table)
+                    })
+                    .collect(),
+            ),
+            SourceTables::Catalog(schemas) => SourceTables::Catalog(
+                schemas
+                    .into_iter()
+                    .map(|(schema, tables)| {
+                        let tables = tables
+                            .into_iter()
+                            .map(|(name, table)| {
+                                let table = f(&[schema.clone(), name.clone()], table);
+                                (name, table)
+                            })
+                            .collect();
+                        (schema, tables)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Where the table at `path` below the data source `source` is registered,
+/// with `path` as in [`SourceTables::tables`].
+pub fn source_table_reference(source: &str, path: &[String]) -> ResolvedTableReference {
+    let (catalog, schema, table) = match path {
+        [] => (DEFAULT_CATALOG, source, source),
+        [table] => (DEFAULT_CATALOG, source, table.as_str()),
+        [schema, table, ..] => (source, schema.as_str(), table.as_str()),
+    };
+    ResolvedTableReference {
+        catalog: catalog.into(),
+        schema: schema.into(),
+        table: table.into(),
+    }
+}
+
+/// Catalog list whose catalogs can also be removed, so a deleted data source
+/// does not leave an empty catalog behind.
+#[derive(Default)]
+pub struct SourceCatalogList {
+    catalogs: RwLock<HashMap<String, Arc<dyn CatalogProvider>>>,
+}
+
+impl SourceCatalogList {
+    pub fn deregister_catalog(&self, name: &str) -> Option<Arc<dyn CatalogProvider>> {
+        self.catalogs.write().unwrap().remove(name)
+    }
+}
+
+impl CatalogProviderList for SourceCatalogList {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn register_catalog(&self, name: String, catalog: Arc<dyn CatalogProvider>) -> Option<Arc<dyn CatalogProvider>> {
+        self.catalogs.write().unwrap().insert(name, catalog)
+    }
+
+    fn catalog_names(&self) -> Vec<String> {
+        self.catalogs.read().unwrap().keys().cloned().collect()
+    }
+
+    fn catalog(&self, name: &str) -> Option<Arc<dyn CatalogProvider>> {
+        self.catalogs.read().unwrap().get(name).cloned()
+    }
+}
+
+/// The default schema. Besides its own tables it resolves the name of every
+/// single-table data source to the table in that source's schema, so
+/// `orders` and `datafusion.orders.orders` are the same table. Those names
+/// are not listed, so every table is listed once.
+struct PublicSchema {
+    tables: MemorySchemaProvider,
+    catalog: Weak<MemoryCatalogProvider>,
+    single_table_sources: RwLock<HashSet<String>>,
+}
+
+impl PublicSchema {
+    fn source_table(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        if !self.single_table_sources.read().unwrap().contains(name) {
+            return None;
+        }
+        self.catalog.upgrade()?.schema(name)
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for PublicSchema {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.tables.table_names()
+    }
+
+    async fn table(&self, name: &str) -> DataFusionResult<Option<Arc<dyn TableProvider>>> {
+        if let Some(table) = self.tables.table(name).await? {
+            return Ok(Some(table));
+        }
+        match self.source_table(name) {
+            Some(schema) => schema.table(name).await,
+            None => Ok(None),
+        }
+    }
+
+    fn register_table(&self, name: String, table: Arc<dyn TableProvider>) -> DataFusionResult<Option<Arc<dyn TableProvider>>> {
+        self.tables.register_table(name, table)
+    }
+
+    fn deregister_table(&self, name: &str) -> DataFusionResult<Option<Arc<dyn TableProvider>>> {
+        self.tables.deregister_table(name)
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.tables.table_exist(name) || self.source_table(name).is_some_and(|schema| schema.table_exist(name))
+    }
+}
+
+/// Whether `table` falls under the data source registered as `source`, in
+/// any of the shapes a source can take.
+pub fn belongs_to_source(table: &ResolvedTableReference, source: &str) -> bool {
+    let (catalog, schema, name) = (&*table.catalog, &*table.schema, &*table.table);
+    (catalog == DEFAULT_CATALOG && schema == DEFAULT_SCHEMA && name == source)
+        || (source != DEFAULT_SCHEMA && catalog == DEFAULT_CATALOG && schema == source)
+        || (source != DEFAULT_CATALOG && catalog == source)
+}
+
+/// Tables read by a view's plan, including those in subqueries.
+pub fn scanned_tables(plan: &LogicalPlan) -> Vec<ResolvedTableReference> {
+    let mut tables = Vec::new();
+    let _ = plan.apply_with_subqueries(|node| {
+        if let LogicalPlan::TableScan(scan) = node {
+            tables.push(scan.table_name.clone().resolve(DEFAULT_CATALOG, DEFAULT_SCHEMA));
+        }
+        Ok(TreeNodeRecursion::Continue)
+    });
+    tables
+}
+
+/// Catalog and runtime shared by every query surface.
+///
+/// Tables registered here are visible to the REST query endpoint, the Flight
+/// server and the data source manager at once. Each request gets its own
+/// `SessionContext` from [`SharedCatalog::session_context`], so no global lock
+/// is held while a query runs.
+pub struct SharedCatalog {
+    catalog_list: Arc<SourceCatalogList>,
+    public_schema: Arc<PublicSchema>,
+    runtime: Arc<RuntimeEnv>,
+    config: SessionConfig,
+    table_functions: RwLock<HashMap<String, Arc<dyn TableFunctionImpl>>>,
+}
+
+impl SharedCatalog {
+    pub fn new() -> Self {
+        Self::with_runtime(Arc::new(RuntimeEnv::default()))
+    }
+
+    pub fn from_config(config: &DataFusionConfig) -> AppResult<Self> {
+        let runtime_config = RuntimeConfig::new()
+            .with_memory_limit(config.max_memory, 1.0)
+            .with_temp_file_path(&config.temp_dir);
+        let runtime = RuntimeEnv::new(runtime_config)?;
+        Ok(Self::with_runtime(Arc::new(runtime)))
+    }
+
+    fn with_runtime(runtime: Arc<RuntimeEnv>) -> Self {
+        let catalog = Arc::new(MemoryCatalogProvider::new());
+        let public_schema = Arc::new(PublicSchema {
+            tables: MemorySchemaProvider::new(),
+            catalog: Arc::downgrade(&catalog),
+            single_table_sources: RwLock::new(HashSet::new()),
+        });
+        catalog
+            .register_schema(DEFAULT_SCHEMA, public_schema.clone())
+            .expect("registering a schema in a memory catalog cannot fail");
+
+        let catalog_list = Arc::new(SourceCatalogList::default());
+        catalog_list.register_catalog(DEFAULT_CATALOG.to_string(), catalog);
+
+        // Derived sessions must not create their own default catalog, otherwise
+        // they would replace the shared one in `catalog_list`.
+        let config = SessionConfig::new()
+            .with_create_default_catalog_and_schema(false)
+            .with_default_catalog_and_schema(DEFAULT_CATALOG, DEFAULT_SCHEMA)
+            .with_information_schema(true);
+
+        SharedCatalog {
+            catalog_list,
+            public_schema,
+            runtime,
+            config,
+            table_functions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a fresh session backed by the shared catalog and runtime.
+    pub fn session_context(&self) -> SessionContext {
+        let state = SessionState::new_with_config_rt_and_catalog_list(
+            self.config.clone(),
+            self.runtime.clone(),
+            self.catalog_list.clone(),
+        );
+        let ctx = SessionContext::new_with_state(state);
+        for (name, function) in self.table_functions.read().unwrap().iter() {
+            ctx.register_udtf(name, function.clone());
+        }
+        ctx
+    }
+
+    /// Makes a table function available in every session created afterwards.
+    pub fn register_table_function(&self, name: &str, function: Arc<dyn TableFunctionImpl>) {
+        self.table_functions
+            .write()
+            .unwrap()
+            .insert(name.to_string(), function);
+    }
+
+    pub fn runtime(&self) -> Arc<RuntimeEnv> {
+        self.runtime.clone()
+    }
+
+    pub fn catalog_list(&self) -> Arc<dyn CatalogProviderList> {
+        self.catalog_list.clone()
+    }
+
+    pub fn default_schema(&self) -> AppResult<Arc<dyn SchemaProvider>> {
+        self.catalog_list
+            .catalog(DEFAULT_CATALOG)
+            .and_then(|catalog| catalog.schema(DEFAULT_SCHEMA))
+            .ok_or_else(|| AppError::InternalError("Default schema is missing".to_string()))
+    }
+
+    /// Registers a table in the default schema, replacing any table of that name.
+    pub fn register_table(
+        &self,
+        name: &str,
+        table: Arc<dyn TableProvider>,
+    ) -> AppResult<Option<Arc<dyn TableProvider>>> {
+        let schema = self.default_schema()?;
+        let previous = schema.deregister_table(name)?;
+        schema.register_table(name.to_string(), table)?;
+        Ok(previous)
+    }
+
+    pub fn deregister_table(&self, name: &str) -> AppResult<Option<Arc<dyn TableProvider>>> {
+        Ok(self.default_schema()?.deregister_table(name)?)
+    }
+
+    /// Registers the tables of a data source, replacing whatever was
+    /// registered under `name` before, whatever its shape.
+    ///
+    /// The new schema or catalog takes the place of the old one in a single
+    /// step, so queries never see the source missing while it is replaced.
+    /// Only a source that changes shape has its old registration torn down,
+    /// after the new one is in place.
+    pub fn register_source(&self, name: &str, tables: SourceTables) -> AppResult<()> {
+        if RESERVED_NAMES.contains(&name) {
+            return Err(AppError::ValidationError(format!(
+                "{} is reserved and cannot name a data source",
+                name
+            )));
+        }
+        let default_catalog = self
+            .catalog_list
+            .catalog(DEFAULT_CATALOG)
+            .ok_or_else(|| AppError::InternalError("Default catalog is missing".to_string()))?;
+        let single_table = matches!(tables, SourceTables::Table(_));
+        match tables {
+            SourceTables::Table(table) => {
+                let tables = vec![(name.to_string(), table)];
+                default_catalog.register_schema(name, schema_from_tables(tables)?)?;
+                self.public_schema
+                    .single_table_sources
+                    .write()
+                    .unwrap()
+                    .insert(name.to_string());
+                self.catalog_list.deregister_catalog(name);
+            }
+            SourceTables::Schema(tables) => {
+                default_catalog.register_schema(name, schema_from_tables(tables)?)?;
+                self.catalog_list.deregister_catalog(name);
+            }
+            SourceTables::Catalog(schemas) => {
+                let catalog = Arc::new(MemoryCatalogProvider::new());
+                for (schema_name, tables) in schemas {
+                    catalog.register_schema(&schema_name, schema_from_tables(tables)?)?;
+                }
+                self.catalog_list.register_catalog(name.to_string(), catalog);
+                default_catalog.deregister_schema(name, true)?;
+            }
+        }
+        if !single_table {
+            self.public_schema.single_table_sources.write().unwrap().remove(name);
+        }
+        self.deregister_table(name)?;
+        Ok(())
+    }
+
+    /// Whether a table in the default schema, a schema in the default catalog
+    /// or a catalog is registered as `name`.
+    pub fn is_registered(&self, name: &str) -> AppResult<bool> {
+        let default_catalog = self.catalog_list.catalog(DEFAULT_CATALOG);
+        Ok(self.default_schema()?.table_exist(name)
+            || default_catalog.is_some_and(|catalog| catalog.schema(name).is_some())
+            || self.catalog_list.catalog(name).is_some())
+    }
+
+    /// Removes the table, schema or catalog registered for a data source.
+    pub fn deregister_source(&self, name: &str) -> AppResult<()> {
+        self.deregister_table(name)?;
+        self.public_schema.single_table_sources.write().unwrap().remove(name);
+        if name != DEFAULT_SCHEMA {
+            if let Some(catalog) = self.catalog_list.catalog(DEFAULT_CATALOG) {
+                catalog.deregister_schema(name, true)?;
+            }
+        }
+        if name != DEFAULT_CATALOG {
+            self.catalog_list.deregister_catalog(name);
+        }
+        Ok(())
+    }
+
+    /// Views that read tables of the data source `source`, directly or
+    /// through other views, as `catalog.schema.view` names.
+    pub async fn dependent_views(&self, source: &str) -> AppResult<Vec<ResolvedTableReference>> {
+        let mut views = Vec::new();
+        for catalog_name in self.catalog_list.catalog_names() {
+            let Some(catalog) = self.catalog_list.catalog(&catalog_name) else {
+                continue;
+            };
+            for schema_name in catalog.schema_names() {
+                let Some(schema) = catalog.schema(&schema_name) else {
+                    continue;
+                };
+                for table_name in schema.table_names() {
+                    let Some(table) = schema.table(&table_name).await? else {
+                        continue;
+                    };
+                    let reference = ResolvedTableReference {
+                        catalog: catalog_name.as_str().into(),
+                        schema: schema_name.as_str().into(),
+                        table: table_name.as_str().into(),
+                    };
+                    if belongs_to_source(&reference, source) {
+                        continue;
+                    }
+                    if let Some(view) = table.as_any().downcast_ref::<ViewTable>() {
+                        views.push((reference, scanned_tables(view.logical_plan())));
+                    }
+                }
+            }
+        }
+
+        // Views on views: keep adding views that read one already found
+        let mut dependents: Vec<ResolvedTableReference> = Vec::new();
+        let mut found: HashSet<String> = HashSet::new();
+        loop {
+            let before = dependents.len();
+            for (view, reads) in &views {
+                if found.contains(&view.to_string()) {
+                    continue;
+                }
+                let depends = reads.iter().any(|table| {
+                    belongs_to_source(table, source) || found.contains(&table.to_string())
+                });
+                if depends {
+                    found.insert(view.to_string());
+                    dependents.push(view.clone());
+                }
+            }
+            if dependents.len() == before {
+                return Ok(dependents);
+            }
+        }
+    }
+
+    /// Tables read by the view registered as `name` in the default schema,
+    /// empty if `name` is not a view.
+    pub async fn view_reads(&self, name: &str) -> AppResult<Vec<ResolvedTableReference>> {
+        let table = self.default_schema()?.table(name).await?;
+        Ok(table
+            .as_ref()
+            .and_then(|table| table.as_any().downcast_ref::<ViewTable>())
+            .map(|view| scanned_tables(view.logical_plan()))
+            .unwrap_or_default())
+    }
+
+    /// Tables registered for the data source `source`, in any of its shapes.
+    pub async fn source_tables(&self, source: &str) -> AppResult<Vec<(ResolvedTableReference, Arc<dyn TableProvider>)>> {
+        let mut tables = Vec::new();
+        for catalog_name in self.catalog_list.catalog_names() {
+            let Some(catalog) = self.catalog_list.catalog(&catalog_name) else {
+                continue;
+            };
+            for schema_name in catalog.schema_names() {
+                let Some(schema) = catalog.schema(&schema_name) else {
+                    continue;
+                };
+                for table_name in schema.table_names() {
+                    let reference = ResolvedTableReference {
+                        catalog: catalog_name.as_str().into(),
+                        schema: schema_name.as_str().into(),
+                        table: table_name.as_str().into(),
+                    };
+                    if !belongs_to_source(&reference, source) {
+                        continue;
+                    }
+                    if let Some(table) = schema.table(&table_name).await? {
+                        tables.push((reference, table));
+                    }
+                }
+            }
+        }
+        tables.sort_by_key(|(reference, _)| reference.to_string());
+        Ok(tables)
+    }
+
+    /// Swaps the table registered at `reference`, which must exist.
+    pub fn replace_table(&self, reference: &ResolvedTableReference, table: Arc<dyn TableProvider>) -> AppResult<()> {
+        let schema = self
+            .catalog_list
+            .catalog(&reference.catalog)
+            .and_then(|catalog| catalog.schema(&reference.schema))
+            .filter(|schema| schema.table_exist(&reference.table))
+            .ok_or_else(|| AppError::ValidationError(format!("Table {} not found", reference)))?;
+        schema.deregister_table(&reference.table)?;
+        schema.register_table(reference.table.to_string(), table)?;
+        Ok(())
+    }
+
+    /// Drops a view found by [`SharedCatalog::dependent_views`], with its
+    /// schema when it is a single-table data source.
+    pub fn deregister_view(&self, view: &ResolvedTableReference) -> AppResult<()> {
+        let is_source = &*view.catalog == DEFAULT_CATALOG
+            && view.schema == view.table
+            && self.public_schema.single_table_sources.read().unwrap().contains(&*view.table);
+        if is_source {
+            return self.deregister_source(&view.table);
+        }
+        if let Some(schema) = self
+            .catalog_list
+            .catalog(&view.catalog)
+            .and_then(|catalog| catalog.schema(&view.schema))
+        {
+            schema.deregister_table(&view.table)?;
+        }
+        Ok(())
+    }
+}
+
+fn schema_from_tables(
+    tables: Vec<(String, Arc<dyn TableProvider>)>,
+) -> AppResult<Arc<dyn SchemaProvider>> {
+    let schema = MemorySchemaProvider::new();
+    for (table_name, table) in tables {
+        schema.register_table(table_name, table)?;
+    }
+    Ok(Arc::new(schema))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::datasource::MemTable;
+
+    #[tokio::test]
+    async fn test_tables_visible_across_sessions() {
+        let catalog = SharedCatalog::new();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let table = MemTable::try_new(schema, vec![vec![batch]]).unwrap();
+
+        catalog
+            .session_context()
+            .register_table("numbers", Arc::new(table))
+            .unwrap();
+
+        let batches = catalog
+            .session_context()
+            .sql("SELECT count(*) FROM numbers")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+
+        catalog.deregister_table("numbers").unwrap();
+        assert!(catalog
+            .session_context()
+            .sql("SELECT * FROM numbers")
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_source_name() {
+        for name in ["orders", "_staging", "sales_2026"] {
+            assert!(validate_source_name(name).is_ok(), "{}", name);
+        }
+        for name in ["Orders", "2026_sales", "my-source", "a b", "", "public", "information_schema"] {
+            assert!(validate_source_name(name).is_err(), "{}", name);
+        }
+        assert!(validate_source_name(&"x".repeat(64)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_source_namespaces() {
+        let catalog = SharedCatalog::new();
+        let table = || {
+            let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+            Arc::new(MemTable::try_new(schema, vec![vec![]]).unwrap()) as Arc<dyn TableProvider>
+        };
+
+        catalog.register_source("events", SourceTables::Table(table())).unwrap();
+        catalog
+            .register_source("crm", SourceTables::Schema(vec![("accounts".to_string(), table())]))
+            .unwrap();
+        catalog
+            .register_source(
+                "erp",
+                SourceTables::Catalog(vec![("billing".to_string(), vec![("accounts".to_string(), table())])]),
+            )
+            .unwrap();
+
+        for sql in [
+            "SELECT * FROM events",
+            "SELECT * FROM datafusion.public.events",
+            "SELECT * FROM events.events",
+            "SELECT * FROM datafusion.events.events",
+            "SELECT * FROM crm.accounts",
+            "SELECT * FROM datafusion.crm.accounts",
+            "SELECT * FROM erp.billing.accounts",
+        ] {
+            assert!(catalog.session_context().sql(sql).await.is_ok(), "{}", sql);
+        }
+        assert!(catalog.is_registered("events").unwrap());
+        assert!(catalog.is_registered("crm").unwrap());
+        assert!(catalog.register_source("public", SourceTables::Table(table())).is_err());
+
+        // A single-table source is listed once, in its own schema
+        let batches = catalog
+            .session_context()
+            .sql("SELECT table_schema FROM information_schema.tables WHERE table_name = 'events'")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let schemas: Vec<String> = batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column(0).as_any().downcast_ref::<arrow::array::StringArray>().unwrap();
+                column.iter().map(|v| v.unwrap().to_string()).collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(schemas, vec!["events".to_string()]);
+
+        catalog.deregister_source("events").unwrap();
+        assert!(!catalog.is_registered("events").unwrap());
+        assert!(catalog.session_context().sql("SELECT * FROM events").await.is_err());
+
+        catalog.deregister_source("erp").unwrap();
+        assert!(!catalog.is_registered("erp").unwrap());
+        assert!(catalog.session_context().sql("SELECT * FROM erp.billing.accounts").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replace_source() {
+        let catalog = SharedCatalog::new();
+        let table = || {
+            let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+            Arc::new(MemTable::try_new(schema, vec![vec![]]).unwrap()) as Arc<dyn TableProvider>
+        };
+
+        catalog.register_source("events", SourceTables::Table(table())).unwrap();
+        let replacement = table();
+        catalog.register_source("events", SourceTables::Table(replacement.clone())).unwrap();
+        let registered = catalog.session_context().table_provider("events").await.unwrap();
+        assert!(Arc::ptr_eq(&registered, &replacement));
+
+        // A change of shape leaves nothing of the old registration behind
+        catalog
+            .register_source("events", SourceTables::Schema(vec![("clicks".to_string(), table())]))
+            .unwrap();
+        assert!(catalog.session_context().sql("SELECT * FROM events.clicks").await.is_ok());
+        assert!(catalog.session_context().sql("SELECT * FROM events").await.is_err());
+
+        catalog
+            .register_source(
+                "events",
+                SourceTables::Catalog(vec![("web".to_string(), vec![("clicks".to_string(), table())])]),
+            )
+            .unwrap();
+        assert!(catalog.session_context().sql("SELECT * FROM events.web.clicks").await.is_ok());
+        assert!(catalog.session_context().sql("SELECT * FROM events.clicks").await.is_err());
+    }
+}
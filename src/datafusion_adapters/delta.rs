@@ -0,0 +1,784 @@
+use super::catalog::SourceTables;
+use super::data_source::DataSourceConfig;
+use super::iceberg::{compare_matches, parse_timestamp_ms};
+use crate::utils::{AppError, AppResult};
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use async_trait::async_trait;
+use datafusion::common::DFSchema;
+use datafusion::datasource::listing::{ListingTableUrl, PartitionedFile};
+use datafusion::datasource::physical_plan::{FileScanConfig, ParquetExec};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result as DataFusionResult;
+use datafusion::execution::context::SessionState;
+use datafusion::execution::runtime_env::RuntimeEnv;
+use datafusion::logical_expr::utils::conjunction;
+use datafusion::logical_expr::{BinaryExpr, Expr, Operator, TableProviderFilterPushDown};
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::scalar::ScalarValue;
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde::Deserialize;
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Highest reader protocol version understood here.
+const MAX_READER_VERSION: i32 = 3;
+
+/// One line of a commit file, or one row of a checkpoint.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Action {
+    add: Option<Add>,
+    remove: Option<Remove>,
+    meta_data: Option<Metadata>,
+    protocol: Option<Protocol>,
+    commit_info: Option<CommitInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Add {
+    path: String,
+    #[serde(default)]
+    partition_values: HashMap<String, Option<String>>,
+    size: i64,
+    stats: Option<String>,
+    deletion_vector: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Remove {
+    path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    pub schema_string: String,
+    #[serde(default)]
+    pub partition_columns: Vec<String>,
+    #[serde(default)]
+    pub configuration: HashMap<String, Option<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Protocol {
+    min_reader_version: i32,
+    reader_features: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommitInfo {
+    timestamp: Option<i64>,
+    in_commit_timestamp: Option<i64>,
+}
+
+/// Column statistics written by Delta writers into `add.stats`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Stats {
+    #[serde(default)]
+    min_values: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    max_values: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum DeltaComplexType {
+    Struct { fields: Vec<DeltaField> },
+    Array {
+        #[serde(rename = "elementType")]
+        element_type: DeltaType,
+        #[serde(rename = "containsNull", default = "default_true")]
+        contains_null: bool,
+    },
+    Map {
+        #[serde(rename = "keyType")]
+        key_type: DeltaType,
+        #[serde(rename = "valueType")]
+        value_type: DeltaType,
+        #[serde(rename = "valueContainsNull", default = "default_true")]
+        value_contains_null: bool,
+    },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum DeltaType {
+    Primitive(String),
+    Complex(Box<DeltaComplexType>),
+}
+
+#[derive(Debug, Deserialize)]
+struct DeltaField {
+    name: String,
+    #[serde(rename = "type")]
+    field_type: DeltaType,
+    #[serde(default = "default_true")]
+    nullable: bool,
+}
+
+fn delta_type_to_arrow(delta_type: &DeltaType) -> AppResult<DataType> {
+    let primitive = match delta_type {
+        DeltaType::Primitive(name) => name.as_str(),
+        DeltaType::Complex(complex) => {
+            return match complex.as_ref() {
+                DeltaComplexType::Struct { fields } => Ok(DataType::Struct(fields_to_arrow(fields)?)),
+                DeltaComplexType::Array {
+                    element_type,
+                    contains_null,
+                } => Ok(DataType::List(Arc::new(Field::new(
+                    "element",
+                    delta_type_to_arrow(element_type)?,
+                    *contains_null,
+                )))),
+                DeltaComplexType::Map {
+                    key_type,
+                    value_type,
+                    value_contains_null,
+                } => {
+                    let entries = Fields::from(vec![
+                        Field::new("key", delta_type_to_arrow(key_type)?, false),
+                        Field::new("value", delta_type_to_arrow(value_type)?, *value_contains_null),
+                    ]);
+                    Ok(DataType::Map(
+                        Arc::new(Field::new("key_value", DataType::Struct(entries), false)),
+                        false,
+                    ))
+                }
+            };
+        }
+    };
+
+    Ok(match primitive {
+        "string" => DataType::Utf8,
+        "long" => DataType::Int64,
+        "integer" => DataType::Int32,
+        "short" => DataType::Int16,
+        "byte" => DataType::Int8,
+        "float" => DataType::Float32,
+        "double" => DataType::Float64,
+        "boolean" => DataType::Boolean,
+        "binary" => DataType::Binary,
+        "date" => DataType::Date32,
+        "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        "timestamp_ntz" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        other => match other.strip_prefix("decimal(").and_then(|s| s.strip_suffix(')')) {
+            Some(args) => {
+                let (precision, scale) = args
+                    .split_once(',')
+                    .and_then(|(p, s)| Some((p.trim().parse().ok()?, s.trim().parse().ok()?)))
+                    .ok_or_else(|| AppError::ValidationError(format!("Invalid Delta type {}", other)))?;
+                DataType::Decimal128(precision, scale)
+            }
+            None => return Err(AppError::ValidationError(format!("Unsupported Delta type {}", other))),
+        },
+    })
+}
+
+fn fields_to_arrow(fields: &[DeltaField]) -> AppResult<Fields> {
+    fields
+        .iter()
+        .map(|f| Ok(Field::new(&f.name, delta_type_to_arrow(&f.field_type)?, f.nullable)))
+        .collect::<AppResult<Vec<_>>>()
+        .map(Fields::from)
+}
+
+/// Arrow schema of a table's `schemaString`.
+pub fn schema_to_arrow(schema_string: &str) -> AppResult<SchemaRef> {
+    let schema: DeltaComplexType = serde_json::from_str(schema_string)
+        .map_err(|e| AppError::ValidationError(format!("Invalid Delta schema: {}", e)))?;
+    match schema {
+        DeltaComplexType::Struct { fields } => Ok(Arc::new(Schema::new(fields_to_arrow(&fields)?))),
+        _ => Err(AppError::ValidationError("A Delta schema must be a struct".to_string())),
+    }
+}
+
+/// Parses a statistics or partition value into a scalar of `data_type`.
+fn parse_value(value: &serde_json::Value, data_type: &DataType) -> Option<ScalarValue> {
+    let text = match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        _ => return None,
+    };
+    ScalarValue::try_from_string(text, data_type).ok()
+}
+
+/// Files in `_delta_log`, by version.
+struct DeltaLog {
+    store: Arc<dyn ObjectStore>,
+    commits: BTreeMap<i64, ObjectMeta>,
+    /// Complete checkpoints only; multi-part ones list every part.
+    checkpoints: BTreeMap<i64, Vec<ObjectMeta>>,
+}
+
+impl DeltaLog {
+    async fn list(store: Arc<dyn ObjectStore>, root: &Path) -> AppResult<Self> {
+        let log = root.child("_delta_log");
+        let listing = store
+            .list_with_delimiter(Some(&log))
+            .await
+            .map_err(|e| AppError::ValidationError(format!("Cannot list the Delta log {}: {}", log, e)))?;
+
+        let mut commits = BTreeMap::new();
+        let mut parts: BTreeMap<i64, (u32, Vec<ObjectMeta>)> = BTreeMap::new();
+        for object in listing.objects {
+            let Some(name) = object.location.filename().map(str::to_string) else {
+                continue;
+            };
+            let mut pieces = name.split('.');
+            let Some(version) = pieces.next().and_then(|v| v.parse::<i64>().ok()) else {
+                continue;
+            };
+            match pieces.collect::<Vec<_>>().as_slice() {
+                ["json"] => {
+                    commits.insert(version, object);
+                }
+                ["checkpoint", "parquet"] => {
+                    parts.insert(version, (1, vec![object]));
+                }
+                ["checkpoint", _, total, "parquet"] => {
+                    if let Ok(total) = total.parse() {
+                        parts.entry(version).or_insert((total, vec![])).1.push(object);
+                    }
+                }
+                // Other checkpoint layouts are replayed from the commits instead
+                _ => {}
+            }
+        }
+        let checkpoints = parts
+            .into_iter()
+            .filter(|(_, (total, objects))| objects.len() == *total as usize)
+            .map(|(version, (_, mut objects))| {
+                objects.sort_by(|a, b| a.location.cmp(&b.location));
+                (version, objects)
+            })
+            .collect();
+
+        let log = DeltaLog {
+            store,
+            commits,
+            checkpoints,
+        };
+        if log.latest_version().is_none() {
+            return Err(AppError::ValidationError(format!("No Delta log found under {}", root)));
+        }
+        Ok(log)
+    }
+
+    fn latest_version(&self) -> Option<i64> {
+        let commit = self.commits.keys().next_back().copied();
+        let checkpoint = self.checkpoints.keys().next_back().copied();
+        commit.max(checkpoint)
+    }
+
+    async fn read(&self, object: &ObjectMeta) -> AppResult<bytes::Bytes> {
+        let read = async { self.store.get(&object.location).await?.bytes().await };
+        read.await
+            .map_err(|e| AppError::InternalError(format!("Failed to read {}: {}", object.location, e)))
+    }
+
+    async fn commit_actions(&self, version: i64) -> AppResult<Vec<Action>> {
+        let object = self.commits.get(&version).ok_or_else(|| {
+            AppError::ValidationError(format!("Delta commit {} is missing from the log", version))
+        })?;
+        let bytes = self.read(object).await?;
+        String::from_utf8_lossy(&bytes)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| {
+                    AppError::ValidationError(format!("Invalid Delta commit {}: {}", object.location, e))
+                })
+            })
+            .collect()
+    }
+
+    /// Checkpoint rows, converted to JSON so they parse like commit actions.
+    async fn checkpoint_actions(&self, version: i64) -> AppResult<Vec<Action>> {
+        let mut actions = Vec::new();
+        for object in &self.checkpoints[&version] {
+            let invalid = |e: String| AppError::ValidationError(format!("Invalid Delta checkpoint {}: {}", object.location, e));
+            let reader = ParquetRecordBatchReaderBuilder::try_new(self.read(object).await?)
+                .and_then(|builder| builder.build())
+                .map_err(|e| invalid(e.to_string()))?;
+            for batch in reader {
+                let batch = batch.map_err(|e| invalid(e.to_string()))?;
+                let mut writer = arrow::json::LineDelimitedWriter::new(Vec::new());
+                writer.write(&batch).map_err(|e| invalid(e.to_string()))?;
+                writer.finish().map_err(|e| invalid(e.to_string()))?;
+                for line in String::from_utf8_lossy(&writer.into_inner()).lines() {
+                    actions.push(serde_json::from_str(line).map_err(|e| invalid(e.to_string()))?);
+                }
+            }
+        }
+        Ok(actions)
+    }
+
+    /// When a commit was made: its in-commit timestamp, the `commitInfo`
+    /// timestamp, or the commit file's modification time.
+    async fn commit_timestamp(&self, version: i64) -> AppResult<i64> {
+        let info = self
+            .commit_actions(version)
+            .await?
+            .into_iter()
+            .find_map(|action| action.commit_info);
+        Ok(info
+            .and_then(|info| info.in_commit_timestamp.or(info.timestamp))
+            .unwrap_or_else(|| self.commits[&version].last_modified.timestamp_millis()))
+    }
+
+    /// Latest version committed at or before `timestamp_ms`.
+    async fn version_as_of(&self, timestamp_ms: i64) -> AppResult<i64> {
+        for &version in self.commits.keys().rev() {
+            if self.commit_timestamp(version).await? <= timestamp_ms {
+                return Ok(version);
+            }
+        }
+        Err(AppError::ValidationError(format!(
+            "No Delta version exists at or before {}",
+            timestamp_ms
+        )))
+    }
+
+    /// Replays the log up to `version` from the latest checkpoint before it.
+    async fn snapshot(&self, version: i64) -> AppResult<(Metadata, Vec<Add>)> {
+        let checkpoint = self.checkpoints.range(..=version).next_back().map(|(v, _)| *v);
+        let mut actions = match checkpoint {
+            Some(checkpoint) => self.checkpoint_actions(checkpoint).await?,
+            None => vec![],
+        };
+        for commit in checkpoint.map_or(0, |c| c + 1)..=version {
+            actions.extend(self.commit_actions(commit).await?);
+        }
+
+        let mut metadata = None;
+        let mut protocol = None;
+        let mut files: HashMap<String, Add> = HashMap::new();
+        for action in actions {
+            if let Some(add) = action.add {
+                files.insert(add.path.clone(), add);
+            }
+            if let Some(remove) = action.remove {
+                files.remove(&remove.path);
+            }
+            metadata = action.meta_data.or(metadata);
+            protocol = action.protocol.or(protocol);
+        }
+
+        if let Some(protocol) = protocol {
+            let column_mapping = protocol
+                .reader_features
+                .iter()
+                .flatten()
+                .any(|feature| feature == "columnMapping");
+            if protocol.min_reader_version > MAX_READER_VERSION || column_mapping {
+                return Err(AppError::ValidationError(format!(
+                    "Delta reader protocol {} with features {:?} is not supported",
+                    protocol.min_reader_version,
+                    protocol.reader_features.unwrap_or_default()
+                )));
+            }
+        }
+        let metadata = metadata
+            .ok_or_else(|| AppError::ValidationError(format!("Delta version {} has no metadata", version)))?;
+        let mapping = metadata.configuration.get("delta.columnMapping.mode").cloned().flatten();
+        if mapping.is_some_and(|mode| mode != "none") {
+            return Err(AppError::ValidationError(
+                "Delta tables with column mapping are not supported".to_string(),
+            ));
+        }
+        if files.values().any(|add| add.deletion_vector.is_some()) {
+            return Err(AppError::ValidationError(
+                "Delta tables with deletion vectors are not supported".to_string(),
+            ));
+        }
+
+        let mut files: Vec<Add> = files.into_values().collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok((metadata, files))
+    }
+}
+
+/// A data file of the table version with the statistics needed for pruning.
+#[derive(Debug, Clone)]
+struct DataFile {
+    location: Path,
+    size: u64,
+    /// Values of the partition columns, in their order.
+    partition_values: Vec<ScalarValue>,
+    min_values: HashMap<String, ScalarValue>,
+    max_values: HashMap<String, ScalarValue>,
+}
+
+/// A Delta table pinned to one version.
+///
+/// Files are pruned with their partition values and the column statistics
+/// in the log before Parquet row-group pruning takes over. Partition columns
+/// follow the data columns in the table schema.
+pub struct DeltaTable {
+    root: ListingTableUrl,
+    version: i64,
+    schema: SchemaRef,
+    file_schema: SchemaRef,
+    partition_columns: Vec<Field>,
+    files: Vec<DataFile>,
+}
+
+impl DeltaTable {
+    /// Opens the table at `location` as of `version`, or its latest version.
+    pub async fn load(runtime: &RuntimeEnv, location: &str, version: Option<i64>) -> AppResult<Self> {
+        let root = ListingTableUrl::parse(format!("{}/", location.trim_end_matches('/')))?;
+        let log = DeltaLog::list(runtime.object_store(root.object_store())?, root.prefix()).await?;
+        Self::load_version(root, &log, version).await
+    }
+
+    async fn load_version(root: ListingTableUrl, log: &DeltaLog, version: Option<i64>) -> AppResult<Self> {
+        let latest = log.latest_version().unwrap_or_default();
+        let version = version.unwrap_or(latest);
+        if version < 0 || version > latest {
+            return Err(AppError::ValidationError(format!(
+                "Delta version {} does not exist; the latest is {}",
+                version, latest
+            )));
+        }
+        let (metadata, adds) = log.snapshot(version).await?;
+
+        let table_schema = schema_to_arrow(&metadata.schema_string)?;
+        let (partition_columns, data_columns): (Vec<Field>, Vec<Field>) = table_schema
+            .fields()
+            .iter()
+            .map(|f| f.as_ref().clone())
+            .partition(|f| metadata.partition_columns.contains(f.name()));
+        let file_schema = Arc::new(Schema::new(data_columns));
+        let schema = Arc::new(Schema::new(
+            file_schema.fields().iter().cloned().chain(partition_columns.iter().cloned().map(Arc::new)).collect::<Fields>(),
+        ));
+
+        let files = adds
+            .into_iter()
+            .map(|add| data_file(&root, &file_schema, &partition_columns, add))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        Ok(DeltaTable {
+            root,
+            version,
+            schema,
+            file_schema,
+            partition_columns,
+            files,
+        })
+    }
+
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    fn file_may_match(&self, file: &DataFile, filters: &[Expr]) -> bool {
+        filters.iter().all(|f| self.predicate_may_match(file, f))
+    }
+
+    fn predicate_may_match(&self, file: &DataFile, expr: &Expr) -> bool {
+        let Expr::BinaryExpr(BinaryExpr { left, op, right }) = expr else {
+            return true;
+        };
+        if *op == Operator::And {
+            return self.predicate_may_match(file, left) && self.predicate_may_match(file, right);
+        }
+        if *op == Operator::Or {
+            return self.predicate_may_match(file, left) || self.predicate_may_match(file, right);
+        }
+
+        let (column, value, op) = match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(c), Expr::Literal(v)) => (c, v, *op),
+            (Expr::Literal(v), Expr::Column(c)) => match op.swap() {
+                Some(swapped) => (c, v, swapped),
+                None => return true,
+            },
+            _ => return true,
+        };
+
+        if let Some(index) = self.partition_columns.iter().position(|f| *f.name() == column.name) {
+            let partition_value = &file.partition_values[index];
+            return partition_value.is_null()
+                || compare_matches(partition_value, partition_value, value, op).unwrap_or(true);
+        }
+        match (file.min_values.get(&column.name), file.max_values.get(&column.name)) {
+            (Some(min), Some(max)) => compare_matches(min, max, value, op).unwrap_or(true),
+            _ => true,
+        }
+    }
+}
+
+fn data_file(root: &ListingTableUrl, file_schema: &Schema, partition_columns: &[Field], add: Add) -> AppResult<DataFile> {
+    let invalid = |e: object_store::path::Error| AppError::ValidationError(format!("Invalid Delta file path {}: {}", add.path, e));
+    // Paths are URIs: relative to the table root, or absolute
+    let location = if add.path.contains("://") {
+        ListingTableUrl::parse(&add.path)?.prefix().clone()
+    } else {
+        let relative = Path::from_url_path(&add.path).map_err(invalid)?;
+        Path::from_iter(root.prefix().parts().chain(relative.parts()))
+    };
+
+    let partition_values = partition_columns
+        .iter()
+        .map(|field| match add.partition_values.get(field.name()).cloned().flatten() {
+            Some(value) => ScalarValue::try_from_string(value, field.data_type()).map_err(AppError::from),
+            None => ScalarValue::try_from(field.data_type()).map_err(AppError::from),
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    // Timestamp statistics are truncated to milliseconds, so they are not used
+    let stats: Stats = add.stats.as_deref().and_then(|s| serde_json::from_str(s).ok()).unwrap_or_default();
+    let bounds = |values: &HashMap<String, serde_json::Value>| -> HashMap<String, ScalarValue> {
+        file_schema
+            .fields()
+            .iter()
+            .filter(|f| !matches!(f.data_type(), DataType::Timestamp(_, _)))
+            .filter_map(|f| Some((f.name().clone(), parse_value(values.get(f.name())?, f.data_type())?)))
+            .collect()
+    };
+
+    Ok(DataFile {
+        location,
+        size: add.size.max(0) as u64,
+        partition_values,
+        min_values: bounds(&stats.min_values),
+        max_values: bounds(&stats.max_values),
+    })
+}
+
+#[async_trait]
+impl TableProvider for DeltaTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        // Used for pruning only, DataFusion still evaluates every filter
+        Ok(vec![TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let files: Vec<&DataFile> = self
+            .files
+            .iter()
+            .filter(|file| self.file_may_match(file, filters))
+            .collect();
+        tracing::debug!(
+            "Delta scan of version {} reads {} of {} files",
+            self.version,
+            files.len(),
+            self.files.len()
+        );
+
+        if files.is_empty() {
+            let projected = match projection {
+                Some(indices) => Arc::new(self.schema.project(indices)?),
+                None => self.schema.clone(),
+            };
+            return Ok(Arc::new(EmptyExec::new(projected)));
+        }
+
+        let partitioned_files = files
+            .iter()
+            .map(|file| {
+                let mut partitioned = PartitionedFile::new(file.location.to_string(), file.size);
+                partitioned.partition_values = file.partition_values.clone();
+                partitioned
+            })
+            .collect();
+        let scan_config = FileScanConfig::new(self.root.object_store(), self.file_schema.clone())
+            .with_file_group(partitioned_files)
+            .with_table_partition_cols(self.partition_columns.clone())
+            .with_projection(projection.cloned())
+            .with_limit(limit);
+
+        let mut builder = ParquetExec::builder(scan_config);
+        if let Some(predicate) = conjunction(filters.to_vec()) {
+            let df_schema = DFSchema::try_from(self.schema.as_ref().clone())?;
+            builder = builder.with_predicate(state.create_physical_expr(predicate, &df_schema)?);
+        }
+        Ok(builder.build_arc())
+    }
+}
+
+/// Opens a Delta table.
+///
+/// `connection_string` is the table root holding `_delta_log`, a local path
+/// or an object store URL. `options["version"]` or `options["timestamp"]`
+/// (RFC 3339 or epoch millis) pin a historical version.
+pub async fn load_tables(runtime: Arc<RuntimeEnv>, config: &DataSourceConfig) -> AppResult<SourceTables> {
+    let root = ListingTableUrl::parse(format!("{}/", config.connection_string.trim_end_matches('/')))?;
+    let log = DeltaLog::list(runtime.object_store(root.object_store())?, root.prefix()).await?;
+
+    let version = match (config.options.get("version"), config.options.get("timestamp")) {
+        (Some(_), Some(_)) => {
+            return Err(AppError::ValidationError(
+                "Set either options.version or options.timestamp, not both".to_string(),
+            ))
+        }
+        (Some(version), None) => Some(
+            version
+                .parse()
+                .map_err(|_| AppError::ValidationError(format!("Invalid version: {}", version)))?,
+        ),
+        (None, Some(timestamp)) => Some(log.version_as_of(parse_timestamp_ms(timestamp)?).await?),
+        (None, None) => None,
+    };
+
+    let table = DeltaTable::load_version(root, &log, version).await?;
+    Ok(SourceTables::Table(Arc::new(table)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafusion_adapters::data_source::DataSourceType;
+    use crate::datafusion_adapters::test_utils::{count, source_config};
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::prelude::{col, lit, SessionContext};
+    use parquet::arrow::ArrowWriter;
+
+    const SCHEMA: &str = r#"{"type":"struct","fields":[
+        {"name":"id","type":"long","nullable":false,"metadata":{}},
+        {"name":"name","type":"string","nullable":true,"metadata":{}},
+        {"name":"region","type":"string","nullable":true,"metadata":{}}
+    ]}"#;
+
+    /// Writes a data file and a commit adding it, as a Delta writer would.
+    fn commit_file(root: &std::path::Path, version: i64, region: &str, ids: &[i64], timestamp: i64) {
+        let dir = root.join(format!("region={}", region));
+        std::fs::create_dir_all(&dir).unwrap();
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("name", DataType::Utf8, true),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(ids.to_vec())),
+                Arc::new(StringArray::from(ids.iter().map(|id| format!("user {}", id)).collect::<Vec<_>>())),
+            ],
+        )
+        .unwrap();
+        let file = dir.join("part-0.parquet");
+        let mut writer = ArrowWriter::try_new(std::fs::File::create(&file).unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let stats = serde_json::json!({
+            "numRecords": ids.len(),
+            "minValues": {"id": ids.iter().min()},
+            "maxValues": {"id": ids.iter().max()}
+        });
+        let mut actions = vec![serde_json::json!({"commitInfo": {"timestamp": timestamp}})];
+        if version == 0 {
+            actions.push(serde_json::json!({"protocol": {"minReaderVersion": 1, "minWriterVersion": 2}}));
+            actions.push(serde_json::json!({"metaData": {
+                "id": "table", "format": {"provider": "parquet", "options": {}},
+                "schemaString": SCHEMA, "partitionColumns": ["region"], "configuration": {}
+            }}));
+        }
+        actions.push(serde_json::json!({"add": {
+            "path": format!("region={}/part-0.parquet", region),
+            "partitionValues": {"region": region},
+            "size": std::fs::metadata(&file).unwrap().len(),
+            "modificationTime": timestamp,
+            "dataChange": true,
+            "stats": stats.to_string()
+        }}));
+
+        let log = root.join("_delta_log");
+        std::fs::create_dir_all(&log).unwrap();
+        let lines: Vec<String> = actions.iter().map(|a| a.to_string()).collect();
+        std::fs::write(log.join(format!("{:020}.json", version)), lines.join("\n")).unwrap();
+    }
+
+    #[test]
+    fn test_schema_to_arrow() {
+        let schema = schema_to_arrow(
+            r#"{"type":"struct","fields":[
+                {"name":"amount","type":"decimal(10,2)","nullable":true,"metadata":{}},
+                {"name":"tags","type":{"type":"array","elementType":"string","containsNull":true},"nullable":true,"metadata":{}},
+                {"name":"attrs","type":{"type":"map","keyType":"string","valueType":"long","valueContainsNull":true},"nullable":true,"metadata":{}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(schema.field(0).data_type(), &DataType::Decimal128(10, 2));
+        assert!(matches!(schema.field(1).data_type(), DataType::List(_)));
+        assert!(matches!(schema.field(2).data_type(), DataType::Map(_, _)));
+        assert!(schema_to_arrow(r#"{"type":"struct","fields":[{"name":"v","type":"variant"}]}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_versions_and_pruning() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        commit_file(root, 0, "eu", &[1, 2, 3], 1_000);
+        commit_file(root, 1, "us", &[10, 11, 12], 2_000);
+        let runtime = Arc::new(RuntimeEnv::default());
+
+        let load = |options: &[(&str, &str)]| {
+            let config = source_config(DataSourceType::Delta, "events", root.display().to_string(), options);
+            let runtime = runtime.clone();
+            async move {
+                match load_tables(runtime, &config).await? {
+                    SourceTables::Table(table) => Ok(table),
+                    _ => panic!("expected a single table"),
+                }
+            }
+        };
+
+        let latest: Arc<dyn TableProvider> = load(&[]).await.unwrap();
+        assert_eq!(latest.schema().field(2).name(), "region");
+        let ctx = SessionContext::new();
+        ctx.register_table("latest", latest.clone()).unwrap();
+        assert_eq!(count(&ctx, "SELECT count(*) FROM latest").await, 6);
+        assert_eq!(count(&ctx, "SELECT count(*) FROM latest WHERE region = 'us'").await, 3);
+
+        let delta = latest.as_any().downcast_ref::<DeltaTable>().unwrap();
+        let kept: Vec<&DataFile> = delta
+            .files
+            .iter()
+            .filter(|f| delta.file_may_match(f, &[col("id").gt(lit(5i64))]))
+            .collect();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].partition_values, vec![ScalarValue::from("us")]);
+
+        ctx.register_table("v0", load(&[("version", "0")]).await.unwrap()).unwrap();
+        ctx.register_table("as_of", load(&[("timestamp", "1500")]).await.unwrap()).unwrap();
+        assert_eq!(count(&ctx, "SELECT count(*) FROM v0").await, 3);
+        assert_eq!(count(&ctx, "SELECT count(*) FROM as_of").await, 3);
+        assert!(load(&[("version", "2")]).await.is_err());
+        assert!(load(&[("timestamp", "500")]).await.is_err());
+    }
+}
@@ -0,0 +1,951 @@
+use super::catalog::SourceTables;
+use super::data_source::{DataSourceConfig, DataSourceType};
+use super::memory::{columns_to_schema, ColumnDefinition};
+use super::object_stores::{register_object_store, OBJECT_STORE_OPTIONS};
+use super::watch::watch_interval;
+use crate::utils::{AppError, AppResult};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::common::{Column, DFSchema};
+use datafusion::config::TableParquetOptions;
+use datafusion::datasource::file_format::avro::AvroFormat;
+use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::datasource::file_format::json::JsonFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result as DataFusionResult;
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::prelude::{cast, lit, nullif};
+use futures::TryStreamExt;
+use object_store::ObjectMeta;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Value Hive writes for a partition whose key is NULL.
+const HIVE_NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Parses `options["partition_columns"]`: comma-separated `name` or
+/// `name:Type` entries, where the Arrow type defaults to `Utf8`.
+pub fn parse_partition_columns(spec: &str) -> AppResult<Vec<(String, DataType)>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((name, data_type)) => {
+                let data_type = data_type.trim().parse::<DataType>().map_err(|_| {
+                    AppError::ValidationError(format!(
+                        "Unknown type {} for partition column {}",
+                        data_type, name
+                    ))
+                })?;
+                Ok((name.trim().to_string(), data_type))
+            }
+            None => Ok((entry.to_string(), DataType::Utf8)),
+        })
+        .collect()
+}
+
+/// Narrowest type that fits every value seen for a partition key. Hive NULL
+/// partitions are left out, as they are read as NULL (see [`HivePartitionTable`]).
+fn infer_partition_type<'a>(values: impl Iterator<Item = &'a str>) -> DataType {
+    let mut all_ints = true;
+    let mut all_dates = true;
+    let mut any = false;
+    for value in values.filter(|value| *value != HIVE_NULL_PARTITION) {
+        any = true;
+        all_ints &= value.parse::<i64>().is_ok();
+        all_dates &= chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok();
+    }
+    match (any, all_ints, all_dates) {
+        (true, true, _) => DataType::Int64,
+        (true, _, true) => DataType::Date32,
+        _ => DataType::Utf8,
+    }
+}
+
+/// Partition columns from `key=value` directories below the table root, in
+/// path order, with types inferred from the values found, and the keys that
+/// have a Hive NULL partition.
+pub async fn discover_partition_columns(
+    state: &SessionState,
+    table_url: &ListingTableUrl,
+    file_extension: &str,
+) -> AppResult<(Vec<(String, DataType)>, Vec<String>)> {
+    let store = state.runtime_env().object_store(table_url.object_store())?;
+    let files: Vec<_> = table_url
+        .list_all_files(state, store.as_ref(), file_extension)
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut keys: Option<Vec<String>> = None;
+    let mut values: Vec<Vec<String>> = Vec::new();
+    for file in &files {
+        let Some(segments) = table_url.strip_prefix(&file.location) else {
+            continue;
+        };
+        let segments: Vec<&str> = segments.collect();
+        let directories = &segments[..segments.len().saturating_sub(1)];
+        let pairs: Vec<(&str, &str)> = directories
+            .iter()
+            .filter_map(|segment| segment.split_once('='))
+            .collect();
+
+        let file_keys: Vec<String> = pairs.iter().map(|(k, _)| k.to_string()).collect();
+        match &keys {
+            None => {
+                values = vec![Vec::new(); file_keys.len()];
+                keys = Some(file_keys);
+            }
+            Some(expected) if *expected != file_keys => {
+                return Err(AppError::ValidationError(format!(
+                    "Inconsistent partition directories: {} does not match {}",
+                    file.location,
+                    expected.join("/")
+                )));
+            }
+            Some(_) => {}
+        }
+        for (i, (_, value)) in pairs.iter().enumerate() {
+            values[i].push(value.to_string());
+        }
+    }
+
+    let mut columns = Vec::new();
+    let mut null_partitions = Vec::new();
+    for (key, values) in keys.unwrap_or_default().into_iter().zip(values) {
+        if values.iter().any(|value| value == HIVE_NULL_PARTITION) {
+            null_partitions.push(key.clone());
+        }
+        columns.push((key.clone(), infer_partition_type(values.iter().map(String::as_str))));
+    }
+    Ok((columns, null_partitions))
+}
+
+const COMMON_OPTIONS: &[&str] = &[
+    "file_extension",
+    "compression",
+    "schema_infer_max_records",
+    "partition_columns",
+    "partition_discovery",
+    "watch",
+    "watch_interval_seconds",
+];
+const CSV_OPTIONS: &[&str] = &["delimiter", "quote", "escape", "header", "null_value"];
+const PARQUET_OPTIONS: &[&str] = &[
+    "pruning",
+    "pushdown_filters",
+    "reorder_filters",
+    "enable_page_index",
+    "skip_metadata",
+];
+
+/// Rejects options that the reader for `source_type` does not understand.
+pub fn validate_options(source_type: &DataSourceType, options: &HashMap<String, String>) -> AppResult<()> {
+    let format_options: &[&str] = match source_type {
+        DataSourceType::CSV => CSV_OPTIONS,
+        DataSourceType::Parquet => PARQUET_OPTIONS,
+        _ => &[],
+    };
+    // Common options some readers have no use for
+    let ignored: &[&str] = match source_type {
+        DataSourceType::Parquet => &["compression"],
+        DataSourceType::Avro => &["compression", "schema_infer_max_records"],
+        _ => &[],
+    };
+    let is_supported = |key: &&str| {
+        (COMMON_OPTIONS.contains(key) && !ignored.contains(key))
+            || format_options.contains(key)
+            || OBJECT_STORE_OPTIONS.contains(key)
+    };
+    let mut unknown: Vec<&str> = options
+        .keys()
+        .map(String::as_str)
+        .filter(|key| !is_supported(key))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+
+    unknown.sort_unstable();
+    let mut supported: Vec<&str> = COMMON_OPTIONS
+        .iter()
+        .chain(format_options)
+        .chain(OBJECT_STORE_OPTIONS)
+        .copied()
+        .filter(is_supported)
+        .collect();
+    supported.sort_unstable();
+    Err(AppError::ValidationError(format!(
+        "Unknown {} option(s): {}. Supported options: {}",
+        source_type.as_str(),
+        unknown.join(", "),
+        supported.join(", ")
+    )))
+}
+
+fn bool_option(options: &HashMap<String, String>, key: &str) -> AppResult<Option<bool>> {
+    options
+        .get(key)
+        .map(|value| match value.to_lowercase().as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(AppError::ValidationError(format!(
+                "Option {} must be true or false, got {}",
+                key, value
+            ))),
+        })
+        .transpose()
+}
+
+fn byte_option(options: &HashMap<String, String>, key: &str) -> AppResult<Option<u8>> {
+    options
+        .get(key)
+        .map(|value| match value.as_str() {
+            "\\t" | "tab" => Ok(b'\t'),
+            v if v.len() == 1 && v.is_ascii() => Ok(v.as_bytes()[0]),
+            _ => Err(AppError::ValidationError(format!(
+                "Option {} must be a single ASCII character, got {:?}",
+                key, value
+            ))),
+        })
+        .transpose()
+}
+
+fn compression_option(options: &HashMap<String, String>) -> AppResult<FileCompressionType> {
+    match options.get("compression").map(|c| c.to_lowercase()).as_deref() {
+        None | Some("uncompressed") | Some("none") => Ok(FileCompressionType::UNCOMPRESSED),
+        Some("gzip") => Ok(FileCompressionType::GZIP),
+        Some("zstd") => Ok(FileCompressionType::ZSTD),
+        Some("bz2") | Some("bzip2") => Ok(FileCompressionType::BZIP2),
+        Some("xz") => Ok(FileCompressionType::XZ),
+        Some(other) => Err(AppError::ValidationError(format!(
+            "Unknown compression {}, expected gzip, zstd, bz2, xz or uncompressed",
+            other
+        ))),
+    }
+}
+
+fn compression_extension(compression: FileCompressionType) -> &'static str {
+    match compression {
+        FileCompressionType::GZIP => ".gz",
+        FileCompressionType::ZSTD => ".zst",
+        FileCompressionType::BZIP2 => ".bz2",
+        FileCompressionType::XZ => ".xz",
+        _ => "",
+    }
+}
+
+/// Builds the reader for `config` and the file extension it lists by default.
+fn file_format(config: &DataSourceConfig) -> AppResult<(Arc<dyn FileFormat>, String)> {
+    let options = &config.options;
+    let infer_records = options
+        .get("schema_infer_max_records")
+        .map(|v| {
+            v.parse::<usize>().map_err(|_| {
+                AppError::ValidationError(format!("Invalid schema_infer_max_records: {}", v))
+            })
+        })
+        .transpose()?;
+    let compression = compression_option(options)?;
+
+    Ok(match config.r#type {
+        DataSourceType::CSV => {
+            let mut format = CsvFormat::default()
+                .with_has_header(bool_option(options, "header")?.unwrap_or(true))
+                .with_file_compression_type(compression);
+            if let Some(delimiter) = byte_option(options, "delimiter")? {
+                format = format.with_delimiter(delimiter);
+            }
+            if let Some(quote) = byte_option(options, "quote")? {
+                format = format.with_quote(quote);
+            }
+            if let Some(escape) = byte_option(options, "escape")? {
+                format = format.with_escape(Some(escape));
+            }
+            if let Some(records) = infer_records {
+                format = format.with_schema_infer_max_rec(records);
+            }
+            (Arc::new(format), format!(".csv{}", compression_extension(compression)))
+        }
+        DataSourceType::JSON => {
+            let mut format = JsonFormat::default().with_file_compression_type(compression);
+            if let Some(records) = infer_records {
+                format = format.with_schema_infer_max_rec(records);
+            }
+            (Arc::new(format), format!(".json{}", compression_extension(compression)))
+        }
+        DataSourceType::Avro => (Arc::new(AvroFormat), ".avro".to_string()),
+        DataSourceType::Parquet => {
+            let mut parquet_options = TableParquetOptions::default();
+            if let Some(value) = bool_option(options, "pruning")? {
+                parquet_options.global.pruning = value;
+            }
+            if let Some(value) = bool_option(options, "pushdown_filters")? {
+                parquet_options.global.pushdown_filters = value;
+            }
+            if let Some(value) = bool_option(options, "reorder_filters")? {
+                parquet_options.global.reorder_filters = value;
+            }
+            if let Some(value) = bool_option(options, "enable_page_index")? {
+                parquet_options.global.enable_page_index = value;
+            }
+            if let Some(value) = bool_option(options, "skip_metadata")? {
+                parquet_options.global.skip_metadata = value;
+            }
+            (
+                Arc::new(ParquetFormat::default().with_options(parquet_options)),
+                ".parquet".to_string(),
+            )
+        }
+        ref other => {
+            return Err(AppError::ValidationError(format!(
+                "{} is not a file format",
+                other.as_str()
+            )))
+        }
+    })
+}
+
+fn is_text(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Utf8 | DataType::LargeUtf8)
+}
+
+/// A file table that reads `token` as NULL in every text column.
+///
+/// The CSV reader only treats empty fields as NULL, so other null tokens are
+/// replaced on top of the scan. Columns holding the token are inferred as
+/// text. Filters are left to DataFusion as they must see the replaced values.
+struct NullTokenTable {
+    inner: Arc<dyn TableProvider>,
+    token: String,
+    schema: SchemaRef,
+}
+
+impl NullTokenTable {
+    fn new(inner: Arc<dyn TableProvider>, token: &str) -> Self {
+        let inner_schema = inner.schema();
+        let fields: Vec<Field> = inner_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let nullable = field.is_nullable() || is_text(field.data_type());
+                field.as_ref().clone().with_nullable(nullable)
+            })
+            .collect();
+        let schema = Arc::new(Schema::new_with_metadata(fields, inner_schema.metadata().clone()));
+        NullTokenTable {
+            inner,
+            token: token.to_string(),
+            schema,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for NullTokenTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let input = self.inner.scan(state, projection, &[], limit).await?;
+        let df_schema = DFSchema::try_from(input.schema().as_ref().clone())?;
+        let exprs = input
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                let column = Expr::Column(Column::new_unqualified(field.name()));
+                let expr = if is_text(field.data_type()) {
+                    nullif(column, lit(self.token.as_str()))
+                } else {
+                    column
+                };
+                Ok((state.create_physical_expr(expr, &df_schema)?, field.name().clone()))
+            })
+            .collect::<DataFusionResult<Vec<_>>>()?;
+        Ok(Arc::new(ProjectionExec::try_new(exprs, input)?))
+    }
+}
+
+/// A file table whose partition columns can have Hive NULL partitions.
+///
+/// DataFusion parses partition values as literals of the column type, which
+/// fails for `__HIVE_DEFAULT_PARTITION__`. The inner table therefore reads
+/// these columns as text, and this table turns the token into NULL and casts
+/// to the inferred type. Filters on other columns still reach the inner table
+/// for pruning; filters on these columns are applied after the mapping.
+struct HivePartitionTable {
+    inner: Arc<dyn TableProvider>,
+    columns: Vec<(String, DataType)>,
+    schema: SchemaRef,
+}
+
+impl HivePartitionTable {
+    fn new(inner: Arc<dyn TableProvider>, columns: Vec<(String, DataType)>) -> Self {
+        let inner_schema = inner.schema();
+        let fields: Vec<Field> = inner_schema
+            .fields()
+            .iter()
+            .map(|field| match columns.iter().find(|(name, _)| name == field.name()) {
+                Some((name, data_type)) => Field::new(name, data_type.clone(), true),
+                None => field.as_ref().clone(),
+            })
+            .collect();
+        let schema = Arc::new(Schema::new_with_metadata(fields, inner_schema.metadata().clone()));
+        HivePartitionTable { inner, columns, schema }
+    }
+
+    fn reads_mapped_column(&self, filter: &Expr) -> bool {
+        filter
+            .column_refs()
+            .iter()
+            .any(|column| self.columns.iter().any(|(name, _)| *name == column.name))
+    }
+}
+
+#[async_trait]
+impl TableProvider for HivePartitionTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(&self, filters: &[&Expr]) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|filter| match self.reads_mapped_column(filter) {
+                true => TableProviderFilterPushDown::Unsupported,
+                false => TableProviderFilterPushDown::Inexact,
+            })
+            .collect())
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let filters: Vec<Expr> = filters
+            .iter()
+            .filter(|filter| !self.reads_mapped_column(filter))
+            .cloned()
+            .collect();
+        let input = self.inner.scan(state, projection, &filters, limit).await?;
+        let df_schema = DFSchema::try_from(input.schema().as_ref().clone())?;
+        let exprs = input
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                let column = Expr::Column(Column::new_unqualified(field.name()));
+                let expr = match self.columns.iter().find(|(name, _)| name == field.name()) {
+                    Some((_, data_type)) => cast(nullif(column, lit(HIVE_NULL_PARTITION)), data_type.clone()),
+                    None => column,
+                };
+                Ok((state.create_physical_expr(expr, &df_schema)?, field.name().clone()))
+            })
+            .collect::<DataFusionResult<Vec<_>>>()?;
+        Ok(Arc::new(ProjectionExec::try_new(exprs, input)?))
+    }
+}
+
+/// Bytes of the first file read when detecting options.
+const SNIFF_BYTES: usize = 64 * 1024;
+const SNIFF_LINES: usize = 20;
+const DELIMITER_CANDIDATES: &[u8] = b",\t;|";
+
+fn count_unquoted(line: &str, delimiter: u8) -> usize {
+    let mut quoted = false;
+    line.bytes()
+        .filter(|b| {
+            if *b == b'"' {
+                quoted = !quoted;
+            }
+            !quoted && *b == delimiter
+        })
+        .count()
+}
+
+/// Picks the candidate found the same, non-zero number of times on every line.
+fn sniff_delimiter(lines: &[&str]) -> Option<u8> {
+    DELIMITER_CANDIDATES
+        .iter()
+        .copied()
+        .filter_map(|delimiter| {
+            let first = count_unquoted(lines.first()?, delimiter);
+            let consistent = lines.iter().all(|line| count_unquoted(line, delimiter) == first);
+            (first > 0 && consistent).then_some((delimiter, first))
+        })
+        .max_by_key(|(_, count)| *count)
+        .map(|(delimiter, _)| delimiter)
+}
+
+/// A first line without numbers followed by one with numbers is a header;
+/// numbers on the first line mean there is none.
+fn sniff_header(lines: &[&str], delimiter: u8) -> Option<bool> {
+    let has_number = |line: &str| {
+        line.split(delimiter as char)
+            .any(|field| field.trim().trim_matches('"').parse::<f64>().is_ok())
+    };
+    match (lines.first(), lines.get(1)) {
+        (Some(first), _) if has_number(first) => Some(false),
+        (Some(_), Some(second)) if has_number(second) => Some(true),
+        _ => None,
+    }
+}
+
+/// Reader options detected from the first file, for options not set explicitly.
+///
+/// `compression` comes from the file suffix; for uncompressed CSV files the
+/// `delimiter` and `header` are guessed from the leading lines.
+pub async fn detect_options(
+    state: &SessionState,
+    config: &DataSourceConfig,
+) -> AppResult<HashMap<String, String>> {
+    validate_options(&config.r#type, &config.options)?;
+    let location =
+        register_object_store(state.runtime_env(), &config.id, &config.connection_string, &config.options).await?;
+    let mut detected = HashMap::new();
+    let base_extension = match config.r#type {
+        DataSourceType::CSV => ".csv",
+        DataSourceType::JSON => ".json",
+        _ => return Ok(detected),
+    };
+
+    let table_url = ListingTableUrl::parse(&location)?;
+    let store = state.runtime_env().object_store(table_url.object_store())?;
+    let extension = config.options.get("file_extension").map(String::as_str);
+    let first = table_url
+        .list_all_files(state, store.as_ref(), extension.unwrap_or(""))
+        .await?
+        .try_filter(|file| {
+            let matches = extension.is_some() || file.location.as_ref().contains(base_extension);
+            futures::future::ready(matches)
+        })
+        .try_next()
+        .await?;
+    let Some(first) = first else {
+        return Ok(detected);
+    };
+
+    let location = first.location.as_ref();
+    let compression = [(".gz", "gzip"), (".zst", "zstd"), (".bz2", "bz2"), (".xz", "xz")]
+        .into_iter()
+        .find(|(suffix, _)| location.ends_with(suffix))
+        .map(|(_, compression)| compression);
+    if !config.options.contains_key("compression") {
+        if let Some(compression) = compression {
+            detected.insert("compression".to_string(), compression.to_string());
+        }
+    }
+    if config.r#type != DataSourceType::CSV || compression.is_some() {
+        return Ok(detected);
+    }
+
+    let sample = store
+        .get_range(&first.location, 0..first.size.min(SNIFF_BYTES))
+        .await
+        .map_err(|e| AppError::InternalError(format!("Failed to read {}: {}", location, e)))?;
+    let text = String::from_utf8_lossy(&sample);
+    let mut lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    if first.size > SNIFF_BYTES {
+        // The last line may be cut off
+        lines.pop();
+    }
+    lines.truncate(SNIFF_LINES);
+
+    let delimiter = match byte_option(&config.options, "delimiter")? {
+        Some(delimiter) => delimiter,
+        None => match sniff_delimiter(&lines) {
+            Some(delimiter) => {
+                let value = if delimiter == b'\t' { "tab".to_string() } else { (delimiter as char).to_string() };
+                detected.insert("delimiter".to_string(), value);
+                delimiter
+            }
+            None => b',',
+        },
+    };
+    if !config.options.contains_key("header") {
+        if let Some(header) = sniff_header(&lines, delimiter) {
+            detected.insert("header".to_string(), header.to_string());
+        }
+    }
+    Ok(detected)
+}
+
+/// Opens a CSV, Parquet, JSON (newline-delimited) or Avro source as a listing table.
+///
+/// `connection_string` is a local path, a `file://`, `s3://` or `http(s)://`
+/// URL, or a directory or glob below one of them. Hive-style `key=value`
+/// directories become partition columns: declared in
+/// `options["partition_columns"]`, or discovered unless
+/// `options["partition_discovery"]` is `false`. Columns given by the user in
+/// `config.columns` skip schema inference.
+pub async fn load_table(state: &SessionState, config: &DataSourceConfig) -> AppResult<SourceTables> {
+    validate_options(&config.r#type, &config.options)?;
+    watch_interval(&config.options)?;
+    let location =
+        register_object_store(state.runtime_env(), &config.id, &config.connection_string, &config.options).await?;
+
+    let table_url = ListingTableUrl::parse(&location)?;
+    let (format, default_extension) = file_format(config)?;
+    let extension = config
+        .options
+        .get("file_extension")
+        .cloned()
+        .unwrap_or(default_extension);
+    let (partition_columns, null_partitions) = match (
+        config.options.get("partition_columns"),
+        config.options.get("partition_discovery").map(String::as_str),
+    ) {
+        (Some(spec), _) => (parse_partition_columns(spec)?, vec![]),
+        (None, None | Some("true")) if table_url.is_collection() => {
+            discover_partition_columns(state, &table_url, &extension).await?
+        }
+        (None, None | Some("true") | Some("false")) => (vec![], vec![]),
+        (None, Some(other)) => {
+            return Err(AppError::ValidationError(format!(
+                "Invalid partition_discovery option: {}",
+                other
+            )))
+        }
+    };
+    // Keys with a Hive NULL partition are listed as text and mapped on top
+    let null_partition_columns: Vec<(String, DataType)> = partition_columns
+        .iter()
+        .filter(|(name, _)| null_partitions.contains(name))
+        .cloned()
+        .collect();
+    let listed_partition_columns = partition_columns
+        .iter()
+        .map(|(name, data_type)| match null_partitions.contains(name) {
+            true => (name.clone(), DataType::Utf8),
+            false => (name.clone(), data_type.clone()),
+        })
+        .collect();
+    let listing_options = ListingOptions::new(format)
+        .with_file_extension(extension)
+        .with_table_partition_cols(listed_partition_columns);
+
+    // Explicit columns replace inference; partition columns are appended by the table
+    let schema = match config.columns.as_deref() {
+        Some(columns) => {
+            let columns: Vec<ColumnDefinition> = serde_json::from_str(columns)
+                .map_err(|e| AppError::ValidationError(format!("Invalid column schema: {}", e)))?;
+            // Partition columns may be listed too
+            let columns: Vec<ColumnDefinition> = columns
+                .into_iter()
+                .filter(|c| !partition_columns.iter().any(|(name, _)| *name == c.name))
+                .collect();
+            columns_to_schema(&columns)?
+        }
+        None => listing_options.infer_schema(state, &table_url).await?,
+    };
+    let listing_config = ListingTableConfig::new(table_url)
+        .with_listing_options(listing_options)
+        .with_schema(schema);
+    let mut table: Arc<dyn TableProvider> = Arc::new(ListingTable::try_new(listing_config)?);
+    if !null_partition_columns.is_empty() {
+        table = Arc::new(HivePartitionTable::new(table, null_partition_columns));
+    }
+
+    let table = match config.options.get("null_value") {
+        Some(token) if !token.is_empty() => Arc::new(NullTokenTable::new(table, token)),
+        _ => table,
+    };
+    Ok(SourceTables::Table(table))
+}
+
+/// Files a CSV, Parquet, JSON or Avro source reads right now.
+pub async fn list_source_files(state: &SessionState, config: &DataSourceConfig) -> AppResult<Vec<ObjectMeta>> {
+    let location =
+        register_object_store(state.runtime_env(), &config.id, &config.connection_string, &config.options).await?;
+    let table_url = ListingTableUrl::parse(&location)?;
+    let (_, default_extension) = file_format(config)?;
+    let extension = config
+        .options
+        .get("file_extension")
+        .cloned()
+        .unwrap_or(default_extension);
+    let store = state.runtime_env().object_store(table_url.object_store())?;
+    let files = table_url
+        .list_all_files(state, store.as_ref(), &extension)
+        .await?
+        .try_collect()
+        .await?;
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafusion_adapters::catalog::SharedCatalog;
+
+    #[tokio::test]
+    async fn test_load_csv_from_file_url() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("a.csv"), "id,city\n1,Oslo\n2,Lima\n").unwrap();
+        std::fs::write(dir.join("b.csv"), "id,city\n3,Pune\n").unwrap();
+
+        let config = DataSourceConfig {
+            name: "cities".to_string(),
+            ..DataSourceConfig::for_test(DataSourceType::CSV, format!("file://{}/", dir.display()), HashMap::new())
+        };
+
+        let catalog = SharedCatalog::new();
+        let ctx = catalog.session_context();
+        let tables = load_table(&ctx.state(), &config).await.unwrap();
+        catalog.register_source("cities", tables).unwrap();
+
+        let batches = catalog
+            .session_context()
+            .sql("SELECT count(*) FROM cities")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let count = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<arrow::array::Int64Array>()
+            .unwrap();
+        assert_eq!(count.value(0), 3);
+    }
+
+    #[test]
+    fn test_parse_partition_columns() {
+        let columns = parse_partition_columns("dt:Date32, region ,year:Int32").unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                ("dt".to_string(), DataType::Date32),
+                ("region".to_string(), DataType::Utf8),
+                ("year".to_string(), DataType::Int32),
+            ]
+        );
+        assert!(parse_partition_columns("dt:Daet").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_discovered_partitions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        for (dt, region, rows) in [
+            ("2026-09-30", "eu", "id\n1\n2\n"),
+            ("2026-10-01", "eu", "id\n3\n"),
+            ("2026-10-01", "us", "id\n4\n5\n"),
+            (HIVE_NULL_PARTITION, "us", "id\n6\n"),
+        ] {
+            let partition = dir.join(format!("dt={}", dt)).join(format!("region={}", region));
+            std::fs::create_dir_all(&partition).unwrap();
+            std::fs::write(partition.join("part-0.csv"), rows).unwrap();
+        }
+
+        let config = DataSourceConfig::for_test(DataSourceType::CSV, format!("{}/", dir.display()), HashMap::new());
+
+        let catalog = SharedCatalog::new();
+        let tables = load_table(&catalog.session_context().state(), &config).await.unwrap();
+        let SourceTables::Table(table) = &tables else {
+            panic!("expected a single table");
+        };
+        let schema = table.schema();
+        assert_eq!(schema.field_with_name("dt").unwrap().data_type(), &DataType::Date32);
+        assert_eq!(schema.field_with_name("region").unwrap().data_type(), &DataType::Utf8);
+        catalog.register_source("events", tables).unwrap();
+
+        for (filter, expected) in [("dt = '2026-10-01' AND region = 'us'", 2), ("dt IS NULL", 1)] {
+            let batches = catalog
+                .session_context()
+                .sql(&format!("SELECT count(*) FROM events WHERE {}", filter))
+                .await
+                .unwrap()
+                .collect()
+                .await
+                .unwrap();
+            let count = batches[0]
+                .column(0)
+                .as_any()
+                .downcast_ref::<arrow::array::Int64Array>()
+                .unwrap();
+            assert_eq!(count.value(0), expected, "{}", filter);
+        }
+    }
+
+    #[test]
+    fn test_infer_partition_type() {
+        assert_eq!(infer_partition_type(["1", "20"].into_iter()), DataType::Int64);
+        assert_eq!(infer_partition_type(["2026-10-01"].into_iter()), DataType::Date32);
+        assert_eq!(infer_partition_type(["1", "eu"].into_iter()), DataType::Utf8);
+        assert_eq!(infer_partition_type(["1", HIVE_NULL_PARTITION].into_iter()), DataType::Int64);
+        assert_eq!(infer_partition_type([HIVE_NULL_PARTITION].into_iter()), DataType::Utf8);
+    }
+
+    #[tokio::test]
+    async fn test_csv_reader_options() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("cities.tsv"), "1\tOslo\n2\tNA\n3\tPune\n").unwrap();
+
+        let options = HashMap::from([
+            ("delimiter".to_string(), "tab".to_string()),
+            ("header".to_string(), "false".to_string()),
+            ("file_extension".to_string(), ".tsv".to_string()),
+            ("null_value".to_string(), "NA".to_string()),
+        ]);
+        let mut config = DataSourceConfig {
+            name: "cities".to_string(),
+            columns: Some(r#"[{"name": "id", "data_type": "Int64"}, {"name": "city", "data_type": "Utf8"}]"#.to_string()),
+            ..DataSourceConfig::for_test(DataSourceType::CSV, format!("{}/", dir.display()), options)
+        };
+
+        let catalog = SharedCatalog::new();
+        let tables = load_table(&catalog.session_context().state(), &config).await.unwrap();
+        catalog.register_source("cities", tables).unwrap();
+
+        let batches = catalog
+            .session_context()
+            .sql("SELECT count(city), sum(id) FROM cities")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let column = |i: usize| {
+            batches[0]
+                .column(i)
+                .as_any()
+                .downcast_ref::<arrow::array::Int64Array>()
+                .unwrap()
+                .value(0)
+        };
+        assert_eq!(column(0), 2);
+        assert_eq!(column(1), 6);
+
+        // Filters see the replaced value and the source stays a table, not a view
+        let provider = catalog.session_context().table_provider("cities").await.unwrap();
+        assert_eq!(provider.table_type(), TableType::Base);
+        let batches = catalog
+            .session_context()
+            .sql("SELECT id FROM cities WHERE city IS NULL")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+
+        config.options.insert("delimeter".to_string(), ";".to_string());
+        assert!(load_table(&catalog.session_context().state(), &config).await.is_err());
+        config.options.remove("delimeter");
+        config.options.insert("compression".to_string(), "lz4".to_string());
+        assert!(load_table(&catalog.session_context().state(), &config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_avro_glob() {
+        use apache_avro::types::Value;
+
+        let schema = apache_avro::Schema::parse_str(
+            r#"{
+                "type": "record", "name": "Event", "fields": [
+                    {"name": "id", "type": "long"},
+                    {"name": "user", "type": ["null", "string"], "default": null},
+                    {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["click", "view"]}},
+                    {"name": "tags", "type": {"type": "array", "items": "string"}},
+                    {"name": "origin", "type": {"type": "record", "name": "Origin", "fields": [
+                        {"name": "city", "type": "string"}
+                    ]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let write_events = |path: std::path::PathBuf, ids: std::ops::Range<i64>| {
+            let mut writer = apache_avro::Writer::new(&schema, Vec::new());
+            for id in ids {
+                let user = match id % 2 {
+                    0 => Value::Union(1, Box::new(Value::String(format!("user{}", id)))),
+                    _ => Value::Union(0, Box::new(Value::Null)),
+                };
+                let kind = ["click", "view"][(id % 2) as usize];
+                writer
+                    .append(Value::Record(vec![
+                        ("id".to_string(), Value::Long(id)),
+                        ("user".to_string(), user),
+                        ("kind".to_string(), Value::Enum((id % 2) as u32, kind.to_string())),
+                        ("tags".to_string(), Value::Array(vec![Value::String("a".to_string()), Value::String("b".to_string())])),
+                        ("origin".to_string(), Value::Record(vec![("city".to_string(), Value::String("Oslo".to_string()))])),
+                    ]))
+                    .unwrap();
+            }
+            std::fs::write(path, writer.into_inner().unwrap()).unwrap();
+        };
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        write_events(dir.join("part-0.avro"), 0..4);
+        write_events(dir.join("part-1.avro"), 4..6);
+        std::fs::write(dir.join("notes.txt"), "not avro").unwrap();
+
+        let mut config = DataSourceConfig {
+            name: "events".to_string(),
+            ..DataSourceConfig::for_test(DataSourceType::Avro, format!("{}/part-*.avro", dir.display()), HashMap::new())
+        };
+        let catalog = SharedCatalog::new();
+        let tables = load_table(&catalog.session_context().state(), &config).await.unwrap();
+        catalog.register_source("events", tables).unwrap();
+
+        let batches = catalog
+            .session_context()
+            .sql("SELECT count(*), count(\"user\"), max(kind), max(origin['city']), max(array_length(tags)) FROM events")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let row = &batches[0];
+        let int = |i: usize| row.column(i).as_any().downcast_ref::<arrow::array::Int64Array>().unwrap().value(0);
+        let string = |i: usize| row.column(i).as_any().downcast_ref::<arrow::array::StringArray>().unwrap().value(0);
+        assert_eq!(int(0), 6);
+        assert_eq!(int(1), 3);
+        assert_eq!(string(2), "view");
+        assert_eq!(string(3), "Oslo");
+        assert_eq!(row.column(4).as_any().downcast_ref::<arrow::array::UInt64Array>().unwrap().value(0), 2);
+
+        config.options.insert("compression".to_string(), "gzip".to_string());
+        assert!(load_table(&catalog.session_context().state(), &config).await.is_err());
+    }
+}
@@ -1,51 +1,205 @@
+use super::catalog::SharedCatalog;
+use super::query_engine::read_only_sql_options;
 use crate::utils::{AppError, AppResult};
-use arrow::ipc::writer::{DictionaryTracker, IpcDataGenerator};
-use arrow::record_batch::RecordBatch;
+use arrow::datatypes::SchemaRef;
 use arrow_flight::encode::FlightDataEncoderBuilder;
 use arrow_flight::error::FlightError;
 use arrow_flight::flight_descriptor::DescriptorType;
 use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::sql::{Any, Command, CommandGetTables, ProstMessageExt};
 use arrow_flight::{
-    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
-    HandshakeRequest, HandshakeResponse, PutResult, SchemaAsIpc, Ticket,
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PutResult, Ticket,
 };
-use datafusion::execution::context::SessionContext;
+use datafusion::datasource::TableType;
+use datafusion::sql::TableReference;
 use futures::Stream;
 use futures::{StreamExt, TryStreamExt};
+use prost::Message;
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use tokio::net::TcpListener;
 use tokio::sync::RwLock;
+use tokio_stream::wrappers::TcpListenerStream;
 use tonic::{Request, Response, Status, Streaming};
 
 pub struct FlightSqlServer {
-    ctx: Arc<RwLock<SessionContext>>,
+    catalog: Arc<SharedCatalog>,
     tasks: Arc<RwLock<HashMap<String, String>>>, // task_id -> SQL query
 }
 
 impl FlightSqlServer {
-    pub fn new() -> Self {
-        let ctx = SessionContext::new();
+    pub fn new(catalog: Arc<SharedCatalog>) -> Self {
         FlightSqlServer {
-            ctx: Arc::new(RwLock::new(ctx)),
+            catalog,
             tasks: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
     pub async fn start_server(&self, port: u16) -> AppResult<()> {
-        let addr = format!("0.0.0.0:{}", port).parse()
-            .map_err(|e| AppError::InternalError(format!("Failed to parse address: {}", e)))?;
-        
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to bind port {}: {}", port, e)))?;
+        self.serve(listener).await
+    }
+
+    /// Serves Flight requests on an already bound listener.
+    pub async fn serve(&self, listener: TcpListener) -> AppResult<()> {
         let service = FlightServiceServer::new(self.clone());
-        
+
         tonic::transport::Server::builder()
             .add_service(service)
-            .serve(addr)
+            .serve_with_incoming(TcpListenerStream::new(listener))
             .await
             .map_err(|e| AppError::InternalError(format!("Flight server error: {}", e)))?;
-            
+
         Ok(())
     }
+
+    /// Resolves a descriptor to SQL: a command carries a Flight SQL
+    /// `CommandStatementQuery` or the plain SQL text, a path names a table as
+    /// `[catalog, schema, table]` or any suffix of it.
+    fn descriptor_sql(descriptor: &FlightDescriptor) -> Result<String, Status> {
+        if descriptor.r#type == DescriptorType::Cmd as i32 {
+            if let Some(Command::CommandStatementQuery(query)) = flight_sql_command(&descriptor.cmd) {
+                return Ok(query.query);
+            }
+            return String::from_utf8(descriptor.cmd.to_vec())
+                .map_err(|_| Status::invalid_argument("Command is not valid UTF-8"));
+        }
+
+        let table = match descriptor.path.as_slice() {
+            [table] => TableReference::bare(table.as_str()),
+            [schema, table] => TableReference::partial(schema.as_str(), table.as_str()),
+            [catalog, schema, table] => {
+                TableReference::full(catalog.as_str(), schema.as_str(), table.as_str())
+            }
+            _ => return Err(Status::invalid_argument("Path must name a table")),
+        };
+        Ok(format!("SELECT * FROM {}", quote_table_reference(&table)))
+    }
+
+    async fn flight_info(&self, descriptor: FlightDescriptor) -> Result<FlightInfo, Status> {
+        if let Some(Command::CommandGetTables(command)) = flight_sql_command(&descriptor.cmd) {
+            let ticket = Ticket::new(command.as_any().encode_to_vec());
+            return FlightInfo::new()
+                .try_with_schema(&command.into_builder().schema())
+                .map_err(|e| Status::internal(format!("Schema serialization error: {}", e)))
+                .map(|info| info.with_descriptor(descriptor).with_endpoint(FlightEndpoint::new().with_ticket(ticket)));
+        }
+
+        let sql = Self::descriptor_sql(&descriptor)?;
+        let ctx = self.catalog.session_context();
+        let df = ctx
+            .sql_with_options(&sql, read_only_sql_options())
+            .await
+            .map_err(|e| Status::invalid_argument(format!("SQL planning error: {}", e)))?;
+        let schema = df.schema().as_arrow().clone();
+
+        FlightInfo::new()
+            .try_with_schema(&schema)
+            .map_err(|e| Status::internal(format!("Schema serialization error: {}", e)))
+            .map(|info| {
+                info.with_descriptor(descriptor)
+                    .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(sql)))
+            })
+    }
+
+    async fn execute_flight_data(&self, sql: &str) -> Result<<Self as FlightService>::DoGetStream, Status> {
+        let ctx = self.catalog.session_context();
+        let df = ctx
+            .sql_with_options(sql, read_only_sql_options())
+            .await
+            .map_err(|e| Status::invalid_argument(format!("SQL planning error: {}", e)))?;
+        let schema = Arc::new(df.schema().as_arrow().clone());
+        let stream = df
+            .execute_stream()
+            .await
+            .map_err(|e| Status::internal(format!("Execution error: {}", e)))?
+            .map_err(|e| FlightError::ExternalError(Box::new(e)));
+
+        let flight_data_stream = FlightDataEncoderBuilder::new()
+            .with_schema(schema)
+            .build(stream)
+            .map_err(|e| Status::internal(format!("Flight data encoding error: {}", e)));
+        Ok(Box::pin(flight_data_stream))
+    }
+
+    /// Answers a Flight SQL `CommandGetTables` from the shared catalog.
+    async fn get_tables_flight_data(&self, command: CommandGetTables) -> Result<<Self as FlightService>::DoGetStream, Status> {
+        let mut builder = command.into_builder();
+        for (path, table_type, schema) in self.tables().await {
+            builder
+                .append(&path[0], &path[1], &path[2], table_type, &schema)
+                .map_err(|e| Status::internal(format!("Failed to list tables: {}", e)))?;
+        }
+        let schema = builder.schema();
+        let batch = builder
+            .build()
+            .map_err(|e| Status::internal(format!("Failed to list tables: {}", e)))?;
+
+        let flight_data_stream = FlightDataEncoderBuilder::new()
+            .with_schema(schema)
+            .build(futures::stream::iter(vec![Ok(batch)]))
+            .map_err(|e| Status::internal(format!("Flight data encoding error: {}", e)));
+        Ok(Box::pin(flight_data_stream))
+    }
+
+    /// Every table of the shared catalog as `[catalog, schema, table]`, its
+    /// Flight SQL table type and schema. Tables that fail to resolve are
+    /// logged and skipped so one broken source does not hide the others.
+    async fn tables(&self) -> Vec<(Vec<String>, &'static str, SchemaRef)> {
+        let catalogs = self.catalog.catalog_list();
+        let mut tables = Vec::new();
+        let mut catalog_names = catalogs.catalog_names();
+        catalog_names.sort();
+        for catalog_name in catalog_names {
+            let Some(catalog) = catalogs.catalog(&catalog_name) else { continue };
+            let mut schema_names = catalog.schema_names();
+            schema_names.sort();
+            for schema_name in schema_names.into_iter().filter(|name| name != "information_schema") {
+                let Some(schema) = catalog.schema(&schema_name) else { continue };
+                let mut table_names = schema.table_names();
+                table_names.sort();
+                for table_name in table_names {
+                    let path = vec![catalog_name.clone(), schema_name.clone(), table_name.clone()];
+                    let provider = match schema.table(&table_name).await {
+                        Ok(Some(provider)) => provider,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            tracing::warn!("Skipping table {} in listing: {}", path.join("."), e);
+                            continue;
+                        }
+                    };
+                    let table_type = match provider.table_type() {
+                        TableType::Base => "TABLE",
+                        TableType::View => "VIEW",
+                        TableType::Temporary => "LOCAL TEMPORARY",
+                    };
+                    tables.push((path, table_type, provider.schema()));
+                }
+            }
+        }
+        tables
+    }
+}
+
+/// Decodes a Flight SQL command wrapped in a protobuf `Any`, if the bytes are one.
+fn flight_sql_command(bytes: &[u8]) -> Option<Command> {
+    let any = Any::decode(bytes).ok()?;
+    Command::try_from(any).ok()
+}
+
+fn quote_table_reference(table: &TableReference) -> String {
+    let quote = |ident: &str| format!("\"{}\"", ident.replace('"', "\"\""));
+    match table {
+        TableReference::Bare { table } => quote(table),
+        TableReference::Partial { schema, table } => format!("{}.{}", quote(schema), quote(table)),
+        TableReference::Full { catalog, schema, table } => {
+            format!("{}.{}.{}", quote(catalog), quote(schema), quote(table))
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -76,45 +230,34 @@ impl FlightService for FlightSqlServer {
         &self,
         _request: Request<Criteria>,
     ) -> Result<Response<Self::ListFlightsStream>, Status> {
-        // Return an empty stream for now
-        let stream = futures::stream::empty();
-        Ok(Response::new(Box::pin(stream)))
+        // One flight per table, described by its path and schema
+        let mut infos = Vec::new();
+        for (path, _, schema) in self.tables().await {
+            let name = path.join(".");
+            let descriptor = FlightDescriptor::new_path(path);
+            let sql = match Self::descriptor_sql(&descriptor) {
+                Ok(sql) => sql,
+                Err(e) => {
+                    tracing::warn!("Skipping table {} in listing: {}", name, e.message());
+                    continue;
+                }
+            };
+            match FlightInfo::new().try_with_schema(&schema) {
+                Ok(info) => infos.push(Ok(info
+                    .with_descriptor(descriptor)
+                    .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(sql))))),
+                Err(e) => tracing::warn!("Skipping table {} in listing: {}", name, e),
+            }
+        }
+
+        Ok(Response::new(Box::pin(futures::stream::iter(infos))))
     }
 
     async fn get_flight_info(
         &self,
         request: Request<FlightDescriptor>,
     ) -> Result<Response<FlightInfo>, Status> {
-        let descriptor = request.into_inner();
-        
-        if descriptor.r#type != DescriptorType::Path as i32 {
-            return Err(Status::invalid_argument("Expected path descriptor"));
-        }
-
-        let path = descriptor.path.join(".");
-        if path.is_empty() {
-            return Err(Status::invalid_argument("Path cannot be empty"));
-        }
-
-        // Execute the query to get schema
-        let ctx = self.ctx.read().await;
-        let df = ctx
-            .sql(&path)
-            .await
-            .map_err(|e| Status::internal(format!("SQL execution error: {}", e)))?;
-        
-        let schema = df.schema().into();
-        
-        // Create FlightInfo
-        let info = FlightInfo {
-            schema: arrow::ipc::writer::IpcMessage::default().0,
-            flight_descriptor: Some(descriptor.clone()),
-            endpoint: vec![],
-            total_records: 0,
-            total_bytes: 0,
-            ordered: false,
-        };
-
+        let info = self.flight_info(request.into_inner()).await?;
         Ok(Response::new(info))
     }
 
@@ -123,37 +266,15 @@ impl FlightService for FlightSqlServer {
         request: Request<Ticket>,
     ) -> Result<Response<Self::DoGetStream>, Status> {
         let ticket = request.into_inner();
-        let sql = String::from_utf8(ticket.ticket)
+        if let Some(Command::CommandGetTables(command)) = flight_sql_command(&ticket.ticket) {
+            let stream = self.get_tables_flight_data(command).await?;
+            return Ok(Response::new(stream));
+        }
+        let sql = String::from_utf8(ticket.ticket.to_vec())
             .map_err(|_| Status::invalid_argument("Invalid ticket"))?;
 
-        // Execute the query
-        let ctx = self.ctx.read().await;
-        let df = ctx
-            .sql(&sql)
-            .await
-            .map_err(|e| Status::internal(format!("SQL execution error: {}", e)))?;
-        
-        let plan = df
-            .create_physical_plan()
-            .await
-            .map_err(|e| Status::internal(format!("Physical plan creation error: {}", e)))?;
-        
-        let task_ctx = ctx.task_ctx();
-        let stream = datafusion::physical_plan::execute_stream(plan, task_ctx)
-            .map_err(|e| Status::internal(format!("Execution stream error: {}", e)))?;
-
-        // Convert RecordBatch stream to FlightData stream
-        let schema = df.schema().into();
-        let schema_bytes = SchemaAsIpc::new(&schema, &Default::default())
-            .try_into()
-            .map_err(|e| Status::internal(format!("Schema serialization error: {}", e)))?;
-
-        // Create a stream that sends the schema first, then the data
-        let flight_data_stream = FlightDataEncoderBuilder::new()
-            .build(stream)
-            .map_err(|e| Status::internal(format!("Flight data encoding error: {}", e)))?;
-
-        Ok(Response::new(Box::pin(flight_data_stream)))
+        let stream = self.execute_flight_data(&sql).await?;
+        Ok(Response::new(stream))
     }
 
     async fn do_put(
@@ -228,30 +349,9 @@ impl FlightService for FlightSqlServer {
                         .ok_or_else(|| Status::not_found("Task not found"))?
                 };
                 
-                // Execute the query
-                let ctx = self.ctx.read().await;
-                let df = ctx
-                    .sql(&sql)
-                    .await
-                    .map_err(|e| Status::internal(format!("SQL execution error: {}", e)))?;
-                
-                let plan = df
-                    .create_physical_plan()
-                    .await
-                    .map_err(|e| Status::internal(format!("Physical plan creation error: {}", e)))?;
-                
-                let task_ctx = ctx.task_ctx();
-                let stream = datafusion::physical_plan::execute_stream(plan, task_ctx)
-                    .map_err(|e| Status::internal(format!("Execution stream error: {}", e)))?;
-
-                // Convert to FlightData and return results
-                let flight_data_stream = FlightDataEncoderBuilder::new()
-                    .build(stream)
-                    .map_err(|e| Status::internal(format!("Flight data encoding error: {}", e)))?;
-
+                let flight_data_stream = self.execute_flight_data(&sql).await?;
                 let result_stream = flight_data_stream
-                    .map(|result| result.map_err(|e| Status::internal(format!("Flight data error: {}", e))))
-                    .map(|result| result.map(|data| arrow_flight::Result { body: data.cmd }));
+                    .map(|result| result.map(|data| arrow_flight::Result { body: data.data_body }));
                 
                 Ok(Response::new(Box::pin(result_stream)))
             }
@@ -281,7 +381,7 @@ impl FlightService for FlightSqlServer {
 impl Clone for FlightSqlServer {
     fn clone(&self) -> Self {
         FlightSqlServer {
-            ctx: self.ctx.clone(),
+            catalog: self.catalog.clone(),
             tasks: self.tasks.clone(),
         }
     }
@@ -293,7 +393,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_flight_server_creation() {
-        let server = FlightSqlServer::new();
-        assert!(server.ctx.read().await != std::sync::Arc::new(RwLock::new(SessionContext::new())));
+        let catalog = Arc::new(SharedCatalog::new());
+        let server = FlightSqlServer::new(catalog.clone());
+        assert!(Arc::ptr_eq(&server.catalog, &catalog));
     }
 }
\ No newline at end of file
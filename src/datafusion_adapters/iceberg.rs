@@ -0,0 +1,936 @@
+use super::catalog::{SourceTables, DEFAULT_CATALOG, DEFAULT_SCHEMA};
+use super::data_source::DataSourceConfig;
+use super::object_stores::scoped_location;
+use super::statistics::unwrap_analyzed;
+use crate::utils::{AppError, AppResult};
+use apache_avro::types::Value as AvroValue;
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use async_trait::async_trait;
+use datafusion::catalog::CatalogProviderList;
+use datafusion::common::DFSchema;
+use datafusion::datasource::function::TableFunctionImpl;
+use datafusion::datasource::listing::{ListingTableUrl, PartitionedFile};
+use datafusion::datasource::physical_plan::{FileScanConfig, ParquetExec};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::SessionState;
+use datafusion::execution::runtime_env::RuntimeEnv;
+use datafusion::logical_expr::utils::conjunction;
+use datafusion::logical_expr::{BinaryExpr, Expr, Operator, TableProviderFilterPushDown};
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::scalar::ScalarValue;
+use datafusion::sql::TableReference;
+use futures::FutureExt;
+use object_store::ObjectStore;
+use serde::Deserialize;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TableMetadata {
+    pub format_version: i32,
+    pub location: String,
+    pub current_snapshot_id: Option<i64>,
+    #[serde(default)]
+    pub snapshots: Vec<Snapshot>,
+    #[serde(default)]
+    pub schemas: Vec<IcebergSchema>,
+    pub current_schema_id: Option<i32>,
+    /// Format v1 tables may only carry a single `schema`.
+    pub schema: Option<IcebergSchema>,
+    #[serde(default)]
+    pub partition_specs: Vec<PartitionSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Snapshot {
+    pub snapshot_id: i64,
+    pub timestamp_ms: i64,
+    pub manifest_list: Option<String>,
+    /// Format v1 snapshots may list manifests inline instead of a manifest list.
+    #[serde(default)]
+    pub manifests: Vec<String>,
+    pub schema_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IcebergSchema {
+    pub schema_id: Option<i32>,
+    pub fields: Vec<NestedField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NestedField {
+    pub id: i32,
+    pub name: String,
+    pub required: bool,
+    #[serde(rename = "type")]
+    pub field_type: IcebergType,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum IcebergType {
+    Primitive(String),
+    Nested(NestedType),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NestedType {
+    Struct {
+        fields: Vec<NestedField>,
+    },
+    List {
+        #[serde(rename = "element-id")]
+        element_id: i32,
+        element: Box<IcebergType>,
+        #[serde(rename = "element-required")]
+        element_required: bool,
+    },
+    Map {
+        #[serde(rename = "key-id")]
+        key_id: i32,
+        key: Box<IcebergType>,
+        #[serde(rename = "value-id")]
+        value_id: i32,
+        value: Box<IcebergType>,
+        #[serde(rename = "value-required")]
+        value_required: bool,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PartitionSpec {
+    pub spec_id: i32,
+    #[serde(default)]
+    pub fields: Vec<PartitionField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PartitionField {
+    pub source_id: i32,
+    pub name: String,
+    pub transform: String,
+}
+
+impl TableMetadata {
+    fn schema_by_id(&self, schema_id: Option<i32>) -> AppResult<&IcebergSchema> {
+        let schema_id = schema_id.or(self.current_schema_id);
+        self.schemas
+            .iter()
+            .find(|s| schema_id.is_some() && s.schema_id == schema_id)
+            .or(self.schema.as_ref())
+            .or_else(|| self.schemas.last())
+            .ok_or_else(|| AppError::ValidationError("Iceberg metadata has no schema".to_string()))
+    }
+
+    pub fn snapshot_by_id(&self, snapshot_id: i64) -> AppResult<&Snapshot> {
+        self.snapshots
+            .iter()
+            .find(|s| s.snapshot_id == snapshot_id)
+            .ok_or_else(|| AppError::ValidationError(format!("Iceberg snapshot {} not found", snapshot_id)))
+    }
+
+    /// Latest snapshot committed at or before `timestamp_ms`.
+    pub fn snapshot_as_of(&self, timestamp_ms: i64) -> AppResult<&Snapshot> {
+        self.snapshots
+            .iter()
+            .filter(|s| s.timestamp_ms <= timestamp_ms)
+            .max_by_key(|s| s.timestamp_ms)
+            .ok_or_else(|| {
+                AppError::ValidationError(format!("No Iceberg snapshot exists at or before {}", timestamp_ms))
+            })
+    }
+
+    pub fn current_snapshot(&self) -> Option<&Snapshot> {
+        self.current_snapshot_id
+            .and_then(|id| self.snapshots.iter().find(|s| s.snapshot_id == id))
+    }
+}
+
+pub fn iceberg_type_to_arrow(field_type: &IcebergType) -> AppResult<DataType> {
+    Ok(match field_type {
+        IcebergType::Primitive(name) => match name.as_str() {
+            "boolean" => DataType::Boolean,
+            "int" => DataType::Int32,
+            "long" => DataType::Int64,
+            "float" => DataType::Float32,
+            "double" => DataType::Float64,
+            "date" => DataType::Date32,
+            "time" => DataType::Time64(TimeUnit::Microsecond),
+            "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, None),
+            "timestamptz" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            "string" => DataType::Utf8,
+            "uuid" => DataType::FixedSizeBinary(16),
+            "binary" => DataType::Binary,
+            other => {
+                if let Some(args) = other.strip_prefix("decimal(").and_then(|s| s.strip_suffix(')')) {
+                    let (precision, scale) = args.split_once(',').ok_or_else(|| {
+                        AppError::ValidationError(format!("Invalid Iceberg type {}", other))
+                    })?;
+                    let precision = precision.trim().parse().map_err(|_| {
+                        AppError::ValidationError(format!("Invalid Iceberg type {}", other))
+                    })?;
+                    let scale = scale.trim().parse().map_err(|_| {
+                        AppError::ValidationError(format!("Invalid Iceberg type {}", other))
+                    })?;
+                    DataType::Decimal128(precision, scale)
+                } else if let Some(len) = other.strip_prefix("fixed[").and_then(|s| s.strip_suffix(']')) {
+                    let len = len.trim().parse().map_err(|_| {
+                        AppError::ValidationError(format!("Invalid Iceberg type {}", other))
+                    })?;
+                    DataType::FixedSizeBinary(len)
+                } else {
+                    return Err(AppError::ValidationError(format!("Unsupported Iceberg type {}", other)));
+                }
+            }
+        },
+        IcebergType::Nested(NestedType::Struct { fields }) => DataType::Struct(fields_to_arrow(fields)?),
+        IcebergType::Nested(NestedType::List {
+            element,
+            element_required,
+            ..
+        }) => DataType::List(Arc::new(Field::new(
+            "element",
+            iceberg_type_to_arrow(element)?,
+            !element_required,
+        ))),
+        IcebergType::Nested(NestedType::Map {
+            key,
+            value,
+            value_required,
+            ..
+        }) => DataType::Map(
+            Arc::new(Field::new(
+                "key_value",
+                DataType::Struct(Fields::from(vec![
+                    Field::new("key", iceberg_type_to_arrow(key)?, false),
+                    Field::new("value", iceberg_type_to_arrow(value)?, !value_required),
+                ])),
+                false,
+            )),
+            false,
+        ),
+    })
+}
+
+fn fields_to_arrow(fields: &[NestedField]) -> AppResult<Fields> {
+    fields
+        .iter()
+        .map(|f| Ok(Field::new(&f.name, iceberg_type_to_arrow(&f.field_type)?, !f.required)))
+        .collect::<AppResult<Vec<_>>>()
+        .map(Fields::from)
+}
+
+pub fn schema_to_arrow(schema: &IcebergSchema) -> AppResult<SchemaRef> {
+    Ok(Arc::new(Schema::new(fields_to_arrow(&schema.fields)?)))
+}
+
+/// A data file from the snapshot's manifests with the statistics needed for pruning.
+#[derive(Debug, Clone)]
+struct DataFile {
+    path: String,
+    size: u64,
+    /// Lower/upper bounds keyed by Iceberg field id, decoded into Arrow scalars.
+    lower_bounds: HashMap<i32, ScalarValue>,
+    upper_bounds: HashMap<i32, ScalarValue>,
+    /// Identity partition values keyed by source field id.
+    partition_values: HashMap<i32, ScalarValue>,
+}
+
+/// An Iceberg table pinned to one snapshot.
+///
+/// Files are pruned with the identity-partition values and column bounds
+/// stored in the manifests before Parquet row-group pruning takes over.
+/// Columns are resolved by name; tables with v2 delete files are rejected.
+pub struct IcebergTable {
+    runtime: Arc<RuntimeEnv>,
+    /// The data source whose object stores serve the table's files.
+    scope: String,
+    metadata: Arc<TableMetadata>,
+    snapshot_id: Option<i64>,
+    schema: SchemaRef,
+    field_ids: HashMap<String, i32>,
+    field_types: HashMap<i32, DataType>,
+    /// Read from the manifests on first use.
+    files: OnceCell<Vec<DataFile>>,
+}
+
+impl IcebergTable {
+    /// Opens the table at a snapshot and reads its manifests.
+    pub async fn load(
+        runtime: Arc<RuntimeEnv>,
+        scope: String,
+        metadata: Arc<TableMetadata>,
+        snapshot_id: Option<i64>,
+    ) -> AppResult<Self> {
+        let table = Self::new(runtime, scope, metadata, snapshot_id)?;
+        table.files().await?;
+        Ok(table)
+    }
+
+    /// Opens the table at a snapshot from metadata alone; the manifests are
+    /// read when the table is first scanned.
+    pub fn new(
+        runtime: Arc<RuntimeEnv>,
+        scope: String,
+        metadata: Arc<TableMetadata>,
+        snapshot_id: Option<i64>,
+    ) -> AppResult<Self> {
+        let snapshot = match snapshot_id {
+            Some(id) => Some(metadata.snapshot_by_id(id)?),
+            None => metadata.current_snapshot(),
+        };
+
+        let iceberg_schema = metadata.schema_by_id(snapshot.and_then(|s| s.schema_id))?;
+        let schema = schema_to_arrow(iceberg_schema)?;
+        let field_ids: HashMap<String, i32> = iceberg_schema
+            .fields
+            .iter()
+            .map(|f| (f.name.clone(), f.id))
+            .collect();
+        let field_types: HashMap<i32, DataType> = iceberg_schema
+            .fields
+            .iter()
+            .filter_map(|f| iceberg_type_to_arrow(&f.field_type).ok().map(|t| (f.id, t)))
+            .collect();
+
+        Ok(IcebergTable {
+            runtime,
+            scope,
+            snapshot_id: snapshot.map(|s| s.snapshot_id),
+            metadata,
+            schema,
+            field_ids,
+            field_types,
+            files: OnceCell::new(),
+        })
+    }
+
+    async fn files(&self) -> AppResult<&[DataFile]> {
+        let files = self
+            .files
+            .get_or_try_init(|| async {
+                match self.snapshot_id {
+                    Some(id) => {
+                        let snapshot = self.metadata.snapshot_by_id(id)?;
+                        read_data_files(&self.runtime, &self.scope, &self.metadata, snapshot, &self.field_types).await
+                    }
+                    None => Ok(vec![]),
+                }
+            })
+            .await?;
+        Ok(files)
+    }
+
+    pub fn snapshot_id(&self) -> Option<i64> {
+        self.snapshot_id
+    }
+
+    pub fn metadata(&self) -> Arc<TableMetadata> {
+        self.metadata.clone()
+    }
+
+    fn file_may_match(&self, file: &DataFile, filters: &[Expr]) -> bool {
+        filters.iter().all(|f| self.predicate_may_match(file, f))
+    }
+
+    fn predicate_may_match(&self, file: &DataFile, expr: &Expr) -> bool {
+        let Expr::BinaryExpr(BinaryExpr { left, op, right }) = expr else {
+            return true;
+        };
+        if *op == Operator::And {
+            return self.predicate_may_match(file, left) && self.predicate_may_match(file, right);
+        }
+        if *op == Operator::Or {
+            return self.predicate_may_match(file, left) || self.predicate_may_match(file, right);
+        }
+
+        let (column, value, op) = match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(c), Expr::Literal(v)) => (c, v, *op),
+            (Expr::Literal(v), Expr::Column(c)) => match op.swap() {
+                Some(swapped) => (c, v, swapped),
+                None => return true,
+            },
+            _ => return true,
+        };
+        let Some(field_id) = self.field_ids.get(&column.name) else {
+            return true;
+        };
+
+        if let Some(partition_value) = file.partition_values.get(field_id) {
+            if let Some(false) = compare_matches(partition_value, partition_value, value, op) {
+                return false;
+            }
+        }
+        match (file.lower_bounds.get(field_id), file.upper_bounds.get(field_id)) {
+            (Some(lower), Some(upper)) => compare_matches(lower, upper, value, op).unwrap_or(true),
+            _ => true,
+        }
+    }
+}
+
+/// Whether a value range `[lower, upper]` can contain rows satisfying `column <op> value`.
+/// Returns `None` when the scalars cannot be compared.
+pub fn compare_matches(lower: &ScalarValue, upper: &ScalarValue, value: &ScalarValue, op: Operator) -> Option<bool> {
+    let value = value.cast_to(&lower.data_type()).ok()?;
+    let below_upper = upper.partial_cmp(&value)?;
+    let above_lower = lower.partial_cmp(&value)?;
+    use std::cmp::Ordering::*;
+    Some(match op {
+        Operator::Eq => above_lower != Greater && below_upper != Less,
+        Operator::Lt => above_lower == Less,
+        Operator::LtEq => above_lower != Greater,
+        Operator::Gt => below_upper == Greater,
+        Operator::GtEq => below_upper != Less,
+        _ => true,
+    })
+}
+
+#[async_trait]
+impl TableProvider for IcebergTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        // Used for pruning only, DataFusion still evaluates every filter
+        Ok(vec![TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let all_files = self.files().await.map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let files: Vec<&DataFile> = all_files
+            .iter()
+            .filter(|file| self.file_may_match(file, filters))
+            .collect();
+        tracing::debug!(
+            "Iceberg scan of snapshot {:?} reads {} of {} files",
+            self.snapshot_id,
+            files.len(),
+            all_files.len()
+        );
+
+        if files.is_empty() {
+            let projected = match projection {
+                Some(indices) => Arc::new(self.schema.project(indices)?),
+                None => self.schema.clone(),
+            };
+            return Ok(Arc::new(EmptyExec::new(projected)));
+        }
+
+        let scoped = |location: &str| -> DataFusionResult<ListingTableUrl> {
+            let location = scoped_location(&self.scope, location)
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            ListingTableUrl::parse(location)
+        };
+        let object_store_url = scoped(&self.metadata.location)?.object_store();
+        let partitioned_files = files
+            .iter()
+            .map(|file| {
+                let url = scoped(&file.path)?;
+                Ok(PartitionedFile::new(url.prefix().to_string(), file.size))
+            })
+            .collect::<DataFusionResult<Vec<_>>>()?;
+
+        let scan_config = FileScanConfig::new(object_store_url, self.schema.clone())
+            .with_file_group(partitioned_files)
+            .with_projection(projection.cloned())
+            .with_limit(limit);
+
+        let mut builder = ParquetExec::builder(scan_config);
+        if let Some(predicate) = conjunction(filters.to_vec()) {
+            let df_schema = DFSchema::try_from(self.schema.as_ref().clone())?;
+            builder = builder.with_predicate(state.create_physical_expr(predicate, &df_schema)?);
+        }
+        Ok(builder.build_arc())
+    }
+}
+
+/// Reads `location` through the object store registered for the data source `scope`.
+async fn read_object(runtime: &RuntimeEnv, scope: &str, location: &str) -> AppResult<bytes::Bytes> {
+    let url = ListingTableUrl::parse(scoped_location(scope, location)?)?;
+    let store = runtime.object_store(url.object_store())?;
+    let bytes = store
+        .get(url.prefix())
+        .await
+        .map_err(|e| AppError::InternalError(format!("Failed to read {}: {}", location, e)))?
+        .bytes()
+        .await
+        .map_err(|e| AppError::InternalError(format!("Failed to read {}: {}", location, e)))?;
+    Ok(bytes)
+}
+
+fn read_avro_records(bytes: &[u8]) -> AppResult<Vec<Vec<(String, AvroValue)>>> {
+    let reader = apache_avro::Reader::new(bytes)
+        .map_err(|e| AppError::InternalError(format!("Invalid Iceberg manifest: {}", e)))?;
+    reader
+        .map(|value| match value {
+            Ok(AvroValue::Record(fields)) => Ok(fields),
+            Ok(other) => Err(AppError::InternalError(format!(
+                "Unexpected Iceberg manifest record: {:?}",
+                other
+            ))),
+            Err(e) => Err(AppError::InternalError(format!("Invalid Iceberg manifest: {}", e))),
+        })
+        .collect()
+}
+
+fn avro_field<'a>(record: &'a [(String, AvroValue)], name: &str) -> Option<&'a AvroValue> {
+    record.iter().find(|(n, _)| n == name).map(|(_, v)| match v {
+        AvroValue::Union(_, inner) => inner.as_ref(),
+        other => other,
+    })
+}
+
+fn avro_long(record: &[(String, AvroValue)], name: &str) -> Option<i64> {
+    match avro_field(record, name)? {
+        AvroValue::Long(v) => Some(*v),
+        AvroValue::Int(v) => Some(*v as i64),
+        _ => None,
+    }
+}
+
+fn avro_string(record: &[(String, AvroValue)], name: &str) -> Option<String> {
+    match avro_field(record, name)? {
+        AvroValue::String(v) => Some(v.clone()),
+        _ => None,
+    }
+}
+
+/// Decodes Iceberg's single-value binary serialization used for column bounds.
+pub fn decode_bound(bytes: &[u8], data_type: &DataType) -> Option<ScalarValue> {
+    let le_i32 = || bytes.get(..4).map(|b| i32::from_le_bytes(b.try_into().unwrap()));
+    let le_i64 = || bytes.get(..8).map(|b| i64::from_le_bytes(b.try_into().unwrap()));
+    Some(match data_type {
+        DataType::Boolean => ScalarValue::Boolean(Some(*bytes.first()? != 0)),
+        DataType::Int32 => ScalarValue::Int32(Some(le_i32()?)),
+        DataType::Int64 => ScalarValue::Int64(Some(le_i64()?)),
+        DataType::Float32 => ScalarValue::Float32(Some(f32::from_le_bytes(bytes.get(..4)?.try_into().ok()?))),
+        DataType::Float64 => ScalarValue::Float64(Some(f64::from_le_bytes(bytes.get(..8)?.try_into().ok()?))),
+        DataType::Date32 => ScalarValue::Date32(Some(le_i32()?)),
+        DataType::Timestamp(TimeUnit::Microsecond, tz) => ScalarValue::TimestampMicrosecond(Some(le_i64()?), tz.clone()),
+        DataType::Utf8 => ScalarValue::Utf8(Some(String::from_utf8(bytes.to_vec()).ok()?)),
+        _ => return None,
+    })
+}
+
+fn avro_to_scalar(value: &AvroValue, data_type: &DataType) -> Option<ScalarValue> {
+    let value = match value {
+        AvroValue::Union(_, inner) => inner.as_ref(),
+        other => other,
+    };
+    let scalar = match value {
+        AvroValue::Null => return ScalarValue::try_from(data_type).ok(),
+        AvroValue::Boolean(v) => ScalarValue::Boolean(Some(*v)),
+        AvroValue::Int(v) | AvroValue::Date(v) => ScalarValue::Int32(Some(*v)),
+        AvroValue::Long(v) | AvroValue::TimestampMicros(v) => ScalarValue::Int64(Some(*v)),
+        AvroValue::Float(v) => ScalarValue::Float32(Some(*v)),
+        AvroValue::Double(v) => ScalarValue::Float64(Some(*v)),
+        AvroValue::String(v) => ScalarValue::Utf8(Some(v.clone())),
+        _ => return None,
+    };
+    scalar.cast_to(data_type).ok()
+}
+
+fn read_bounds(
+    data_file: &[(String, AvroValue)],
+    name: &str,
+    field_types: &HashMap<i32, DataType>,
+) -> HashMap<i32, ScalarValue> {
+    let mut bounds = HashMap::new();
+    // Avro maps with int keys are stored as arrays of {key, value} records
+    if let Some(AvroValue::Array(entries)) = avro_field(data_file, name) {
+        for entry in entries {
+            if let AvroValue::Record(kv) = entry {
+                let (Some(AvroValue::Int(key)), Some(AvroValue::Bytes(value))) =
+                    (avro_field(kv, "key"), avro_field(kv, "value"))
+                else {
+                    continue;
+                };
+                if let Some(scalar) = field_types.get(key).and_then(|t| decode_bound(value, t)) {
+                    bounds.insert(*key, scalar);
+                }
+            }
+        }
+    }
+    bounds
+}
+
+async fn read_data_files(
+    runtime: &RuntimeEnv,
+    scope: &str,
+    metadata: &TableMetadata,
+    snapshot: &Snapshot,
+    field_types: &HashMap<i32, DataType>,
+) -> AppResult<Vec<DataFile>> {
+    let manifest_paths: Vec<String> = match &snapshot.manifest_list {
+        Some(manifest_list) => {
+            let bytes = read_object(runtime, scope, manifest_list).await?;
+            let mut paths = Vec::new();
+            for record in read_avro_records(&bytes)? {
+                // content: 0 = data, 1 = deletes (format v2)
+                if avro_long(&record, "content").unwrap_or(0) != 0 {
+                    return Err(AppError::ValidationError(
+                        "Iceberg tables with delete files are not supported".to_string(),
+                    ));
+                }
+                if let Some(path) = avro_string(&record, "manifest_path") {
+                    paths.push(path);
+                }
+            }
+            paths
+        }
+        None => snapshot.manifests.clone(),
+    };
+
+    let identity_partitions: HashMap<String, i32> = metadata
+        .partition_specs
+        .iter()
+        .flat_map(|spec| spec.fields.iter())
+        .filter(|f| f.transform == "identity")
+        .map(|f| (f.name.clone(), f.source_id))
+        .collect();
+
+    let mut files = Vec::new();
+    for manifest_path in manifest_paths {
+        let bytes = read_object(runtime, scope, &manifest_path).await?;
+        for entry in read_avro_records(&bytes)? {
+            // status: 0 = existing, 1 = added, 2 = deleted
+            if avro_long(&entry, "status") == Some(2) {
+                continue;
+            }
+            let Some(AvroValue::Record(data_file)) = avro_field(&entry, "data_file") else {
+                continue;
+            };
+            if avro_long(data_file, "content").unwrap_or(0) != 0 {
+                return Err(AppError::ValidationError(
+                    "Iceberg tables with delete files are not supported".to_string(),
+                ));
+            }
+            let format = avro_string(data_file, "file_format").unwrap_or_default();
+            if !format.eq_ignore_ascii_case("parquet") {
+                return Err(AppError::ValidationError(format!(
+                    "Unsupported Iceberg data file format {}",
+                    format
+                )));
+            }
+
+            let mut partition_values = HashMap::new();
+            if let Some(AvroValue::Record(partition)) = avro_field(data_file, "partition") {
+                for (name, value) in partition {
+                    let Some(source_id) = identity_partitions.get(name) else {
+                        continue;
+                    };
+                    if let Some(scalar) = field_types
+                        .get(source_id)
+                        .and_then(|t| avro_to_scalar(value, t))
+                    {
+                        partition_values.insert(*source_id, scalar);
+                    }
+                }
+            }
+
+            files.push(DataFile {
+                path: avro_string(data_file, "file_path").unwrap_or_default(),
+                size: avro_long(data_file, "file_size_in_bytes").unwrap_or(0) as u64,
+                lower_bounds: read_bounds(data_file, "lower_bounds", field_types),
+                upper_bounds: read_bounds(data_file, "upper_bounds", field_types),
+                partition_values,
+            });
+        }
+    }
+
+    Ok(files)
+}
+
+/// Resolves the metadata file for a table location: an explicit
+/// `*.metadata.json` path, the version in `metadata/version-hint.text`, or the
+/// highest-numbered metadata file in `metadata/`.
+async fn resolve_metadata_location(runtime: &RuntimeEnv, scope: &str, location: &str) -> AppResult<String> {
+    if location.ends_with(".metadata.json") {
+        return Ok(location.to_string());
+    }
+
+    let base = location.trim_end_matches('/');
+    if let Ok(hint) = read_object(runtime, scope, &format!("{}/metadata/version-hint.text", base)).await {
+        let version = String::from_utf8_lossy(&hint).trim().to_string();
+        return Ok(format!("{}/metadata/v{}.metadata.json", base, version));
+    }
+
+    let url = ListingTableUrl::parse(scoped_location(scope, &format!("{}/metadata/", base))?)?;
+    let store = runtime.object_store(url.object_store())?;
+    let listing = store
+        .list_with_delimiter(Some(url.prefix()))
+        .await
+        .map_err(|e| AppError::InternalError(format!("Failed to list {}: {}", base, e)))?;
+
+    let version_of = |name: &str| -> Option<u64> {
+        let stem = name.strip_suffix(".metadata.json")?;
+        let digits = stem.trim_start_matches('v');
+        digits.split('-').next()?.parse().ok()
+    };
+    listing
+        .objects
+        .iter()
+        .filter_map(|o| {
+            let name = o.location.filename()?.to_string();
+            version_of(&name).map(|v| (v, name))
+        })
+        .max_by_key(|(v, _)| *v)
+        .map(|(_, name)| format!("{}/metadata/{}", base, name))
+        .ok_or_else(|| AppError::ValidationError(format!("No Iceberg metadata found under {}", base)))
+}
+
+/// Looks up the current metadata location in an Iceberg SQL (JDBC) catalog.
+async fn metadata_location_from_sql_catalog(config: &DataSourceConfig, catalog_url: &str) -> AppResult<String> {
+    let catalog_name = config.options.get("catalog_name").map(String::as_str).unwrap_or("default");
+    let namespace = config
+        .options
+        .get("namespace")
+        .ok_or_else(|| AppError::ValidationError("options.namespace is required with catalog_url".to_string()))?;
+    let table = config
+        .options
+        .get("table")
+        .ok_or_else(|| AppError::ValidationError("options.table is required with catalog_url".to_string()))?;
+
+    let sql = "SELECT metadata_location FROM iceberg_tables WHERE catalog_name = $1 AND table_namespace = $2 AND table_name = $3";
+    let location: Option<String> = if catalog_url.starts_with("sqlite:") {
+        let pool = sqlx::SqlitePool::connect(catalog_url).await?;
+        sqlx::query_scalar(sql)
+            .bind(catalog_name)
+            .bind(namespace)
+            .bind(table)
+            .fetch_optional(&pool)
+            .await?
+    } else {
+        let pool = sqlx::PgPool::connect(catalog_url).await?;
+        sqlx::query_scalar(sql)
+            .bind(catalog_name)
+            .bind(namespace)
+            .bind(table)
+            .fetch_optional(&pool)
+            .await?
+    };
+
+    location.ok_or_else(|| {
+        AppError::ValidationError(format!("Iceberg table {}.{} not found in catalog", namespace, table))
+    })
+}
+
+pub fn parse_timestamp_ms(value: &str) -> AppResult<i64> {
+    if let Ok(ms) = value.parse::<i64>() {
+        return Ok(ms);
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.timestamp_millis())
+        .map_err(|_| AppError::ValidationError(format!("Invalid timestamp {}", value)))
+}
+
+pub async fn load_metadata(runtime: &RuntimeEnv, config: &DataSourceConfig) -> AppResult<TableMetadata> {
+    let metadata_location = match config.options.get("catalog_url") {
+        Some(catalog_url) => metadata_location_from_sql_catalog(config, catalog_url).await?,
+        None => resolve_metadata_location(runtime, &config.id, &config.connection_string).await?,
+    };
+    let bytes = read_object(runtime, &config.id, &metadata_location).await?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| AppError::ValidationError(format!("Invalid Iceberg metadata {}: {}", metadata_location, e)))
+}
+
+/// Opens an Iceberg table.
+///
+/// `connection_string` is the table location (or a `*.metadata.json` path);
+/// alternatively `options["catalog_url"]`, `namespace` and `table` look the
+/// table up in an Iceberg SQL catalog. `options["snapshot_id"]` or
+/// `options["as_of"]` (RFC 3339 or epoch millis) pin a historical snapshot.
+pub async fn load_tables(runtime: Arc<RuntimeEnv>, config: &DataSourceConfig) -> AppResult<SourceTables> {
+    let metadata = Arc::new(load_metadata(&runtime, config).await?);
+
+    let snapshot_id = match (config.options.get("snapshot_id"), config.options.get("as_of")) {
+        (Some(id), _) => Some(
+            id.parse()
+                .map_err(|_| AppError::ValidationError(format!("Invalid snapshot_id: {}", id)))?,
+        ),
+        (None, Some(as_of)) => Some(metadata.snapshot_as_of(parse_timestamp_ms(as_of)?)?.snapshot_id),
+        (None, None) => None,
+    };
+
+    let table = IcebergTable::load(runtime, config.id.clone(), metadata, snapshot_id).await?;
+    Ok(SourceTables::Table(Arc::new(table)))
+}
+
+/// `iceberg_snapshot('<table>', <snapshot id | 'timestamp'>)`: reads a
+/// registered Iceberg table as of a snapshot id or a point in time.
+pub struct IcebergSnapshotFunction {
+    catalog_list: Arc<dyn CatalogProviderList>,
+}
+
+impl IcebergSnapshotFunction {
+    pub fn new(catalog_list: Arc<dyn CatalogProviderList>) -> Self {
+        IcebergSnapshotFunction { catalog_list }
+    }
+
+    fn lookup(&self, name: &str) -> DataFusionResult<Arc<dyn TableProvider>> {
+        let reference = TableReference::from(name).resolve(DEFAULT_CATALOG, DEFAULT_SCHEMA);
+        let schema = self
+            .catalog_list
+            .catalog(&reference.catalog)
+            .and_then(|c| c.schema(&reference.schema))
+            .ok_or_else(|| DataFusionError::Plan(format!("Table {} not found", name)))?;
+        // Schemas in the shared catalog are in-memory, so this is ready at once
+        schema
+            .table(&reference.table)
+            .now_or_never()
+            .ok_or_else(|| DataFusionError::Plan(format!("Table {} cannot be resolved while planning", name)))??
+            .ok_or_else(|| DataFusionError::Plan(format!("Table {} not found", name)))
+    }
+}
+
+impl TableFunctionImpl for IcebergSnapshotFunction {
+    fn call(&self, args: &[Expr]) -> DataFusionResult<Arc<dyn TableProvider>> {
+        let (name, version) = match args {
+            [Expr::Literal(ScalarValue::Utf8(Some(name))), Expr::Literal(version)] => (name, version),
+            _ => {
+                return Err(DataFusionError::Plan(
+                    "iceberg_snapshot expects a table name and a snapshot id or timestamp".to_string(),
+                ))
+            }
+        };
+
+        // Analyzed tables are registered behind a wrapper reporting their statistics
+        let provider = unwrap_analyzed(self.lookup(name)?);
+        let table = provider
+            .as_any()
+            .downcast_ref::<IcebergTable>()
+            .ok_or_else(|| DataFusionError::Plan(format!("{} is not an Iceberg table", name)))?;
+        let metadata = table.metadata();
+
+        let snapshot_id = match version {
+            ScalarValue::Int64(Some(id)) => *id,
+            ScalarValue::Utf8(Some(timestamp)) => {
+                let timestamp_ms =
+                    parse_timestamp_ms(timestamp).map_err(|e| DataFusionError::Plan(e.to_string()))?;
+                metadata
+                    .snapshot_as_of(timestamp_ms)
+                    .map_err(|e| DataFusionError::Plan(e.to_string()))?
+                    .snapshot_id
+            }
+            other => {
+                return Err(DataFusionError::Plan(format!(
+                    "Invalid snapshot argument for iceberg_snapshot: {}",
+                    other
+                )))
+            }
+        };
+
+        // The snapshot's manifests are read when the query scans it
+        let snapshot = IcebergTable::new(table.runtime.clone(), table.scope.clone(), metadata, Some(snapshot_id))
+            .map_err(|e| DataFusionError::Plan(e.to_string()))?;
+        Ok(Arc::new(snapshot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = r#"{
+        "format-version": 2,
+        "table-uuid": "9c12d441-03fe-4693-9a96-a0705ddf69c1",
+        "location": "file:///warehouse/db/events",
+        "last-updated-ms": 1760000000000,
+        "current-snapshot-id": 2,
+        "current-schema-id": 0,
+        "schemas": [{
+            "type": "struct",
+            "schema-id": 0,
+            "fields": [
+                {"id": 1, "name": "id", "required": true, "type": "long"},
+                {"id": 2, "name": "amount", "required": false, "type": "decimal(10, 2)"},
+                {"id": 3, "name": "tags", "required": false, "type": {
+                    "type": "list", "element-id": 4, "element": "string", "element-required": false
+                }}
+            ]
+        }],
+        "partition-specs": [{"spec-id": 0, "fields": [
+            {"source-id": 1, "field-id": 1000, "name": "id", "transform": "identity"}
+        ]}],
+        "snapshots": [
+            {"snapshot-id": 1, "timestamp-ms": 1750000000000, "manifest-list": "file:///warehouse/db/events/metadata/snap-1.avro"},
+            {"snapshot-id": 2, "timestamp-ms": 1760000000000, "manifest-list": "file:///warehouse/db/events/metadata/snap-2.avro"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_metadata_and_schema() {
+        let metadata: TableMetadata = serde_json::from_str(METADATA).unwrap();
+        assert_eq!(metadata.current_snapshot().unwrap().snapshot_id, 2);
+
+        let schema = schema_to_arrow(metadata.schema_by_id(None).unwrap()).unwrap();
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+        assert!(!schema.field(0).is_nullable());
+        assert_eq!(schema.field(1).data_type(), &DataType::Decimal128(10, 2));
+        assert!(matches!(schema.field(2).data_type(), DataType::List(_)));
+    }
+
+    #[test]
+    fn test_snapshot_as_of() {
+        let metadata: TableMetadata = serde_json::from_str(METADATA).unwrap();
+        assert_eq!(metadata.snapshot_as_of(1755000000000).unwrap().snapshot_id, 1);
+        assert_eq!(metadata.snapshot_as_of(1770000000000).unwrap().snapshot_id, 2);
+        assert!(metadata.snapshot_as_of(1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_manifests_are_read_on_load_or_scan() {
+        let metadata: Arc<TableMetadata> = Arc::new(serde_json::from_str(METADATA).unwrap());
+        let runtime = Arc::new(RuntimeEnv::default());
+
+        // Pinning a snapshot only needs the metadata
+        let table = IcebergTable::new(runtime.clone(), "events".to_string(), metadata.clone(), Some(1)).unwrap();
+        assert_eq!(table.snapshot_id(), Some(1));
+        assert_eq!(table.schema().fields().len(), 3);
+        assert!(IcebergTable::new(runtime.clone(), "events".to_string(), metadata.clone(), Some(3)).is_err());
+
+        // The manifest list does not exist
+        assert!(table.files().await.is_err());
+        assert!(IcebergTable::load(runtime, "events".to_string(), metadata, Some(1)).await.is_err());
+    }
+
+    #[test]
+    fn test_bounds_pruning() {
+        let lower = decode_bound(&10i64.to_le_bytes(), &DataType::Int64).unwrap();
+        let upper = decode_bound(&20i64.to_le_bytes(), &DataType::Int64).unwrap();
+
+        assert_eq!(compare_matches(&lower, &upper, &ScalarValue::Int64(Some(15)), Operator::Eq), Some(true));
+        assert_eq!(compare_matches(&lower, &upper, &ScalarValue::Int64(Some(25)), Operator::Eq), Some(false));
+        assert_eq!(compare_matches(&lower, &upper, &ScalarValue::Int32(Some(10)), Operator::Lt), Some(false));
+        assert_eq!(compare_matches(&lower, &upper, &ScalarValue::Int64(Some(20)), Operator::GtEq), Some(true));
+    }
+}
@@ -0,0 +1,444 @@
+use super::catalog::SourceTables;
+use super::data_source::DataSourceConfig;
+use crate::utils::{AppError, AppResult};
+use arrow::buffer::Buffer;
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::ipc::convert::fb_to_schema;
+use arrow::ipc::reader::{read_footer_length, FileDecoder, FileReader, StreamReader};
+use arrow::ipc::{root_as_footer, Block};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::SessionState;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::streaming::{PartitionStream, StreamingTableExec};
+use datafusion::physical_plan::ExecutionPlan;
+use memmap2::Mmap;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+use std::sync::Arc;
+
+const FILE_MAGIC: &[u8; 6] = b"ARROW1";
+
+/// Arrow IPC encoding of a file on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcFormat {
+    /// Random-access file format (Feather v2), with a footer.
+    File,
+    /// Streaming format, read front to back.
+    Stream,
+}
+
+impl IpcFormat {
+    fn parse(value: &str) -> AppResult<Self> {
+        match value.to_lowercase().as_str() {
+            "file" | "feather" => Ok(IpcFormat::File),
+            "stream" => Ok(IpcFormat::Stream),
+            other => Err(AppError::ValidationError(format!("Unknown Arrow IPC format: {}", other))),
+        }
+    }
+
+    /// Detects the format from the leading magic bytes.
+    pub fn detect(path: &Path) -> AppResult<Self> {
+        let mut magic = [0u8; 6];
+        let mut file = File::open(path)
+            .map_err(|e| AppError::ValidationError(format!("Cannot open {}: {}", path.display(), e)))?;
+        match file.read_exact(&mut magic) {
+            Ok(()) if &magic == FILE_MAGIC => Ok(IpcFormat::File),
+            _ => Ok(IpcFormat::Stream),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IpcFile {
+    path: PathBuf,
+    format: IpcFormat,
+}
+
+/// One or more Arrow IPC files sharing a schema, read in place.
+///
+/// Each file is scanned as its own partition. With `mmap` enabled, IPC files
+/// are decoded straight from the mapped pages without copying.
+pub struct ArrowIpcTable {
+    schema: SchemaRef,
+    files: Vec<IpcFile>,
+    mmap: bool,
+}
+
+impl ArrowIpcTable {
+    pub fn try_new(paths: Vec<PathBuf>, format: Option<IpcFormat>, mmap: bool) -> AppResult<Self> {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let format = match format {
+                    Some(format) => format,
+                    None => IpcFormat::detect(&path)?,
+                };
+                Ok(IpcFile { path, format })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        let first = files
+            .first()
+            .ok_or_else(|| AppError::ValidationError("No Arrow IPC files found".to_string()))?;
+        let schema = read_schema(first)?;
+        for file in &files[1..] {
+            if read_schema(file)?.fields() != schema.fields() {
+                return Err(AppError::ValidationError(format!(
+                    "{} does not match the schema of {}",
+                    file.path.display(),
+                    first.path.display()
+                )));
+            }
+        }
+
+        Ok(ArrowIpcTable { schema, files, mmap })
+    }
+}
+
+fn read_schema(file: &IpcFile) -> AppResult<SchemaRef> {
+    let reader = BufReader::new(File::open(&file.path).map_err(|e| {
+        AppError::ValidationError(format!("Cannot open {}: {}", file.path.display(), e))
+    })?);
+    let schema = match file.format {
+        IpcFormat::File => FileReader::try_new(reader, None).map(|r| r.schema()),
+        IpcFormat::Stream => StreamReader::try_new(reader, None).map(|r| r.schema()),
+    };
+    schema.map_err(|e| AppError::ValidationError(format!("Invalid Arrow IPC file {}: {}", file.path.display(), e)))
+}
+
+#[async_trait]
+impl TableProvider for ArrowIpcTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let projected_schema = match projection {
+            Some(indices) => Arc::new(self.schema.project(indices)?),
+            None => self.schema.clone(),
+        };
+
+        let partitions = self
+            .files
+            .iter()
+            .map(|file| {
+                Arc::new(IpcPartition {
+                    file: file.clone(),
+                    mmap: self.mmap,
+                    projection: projection.cloned(),
+                    schema: projected_schema.clone(),
+                }) as Arc<dyn PartitionStream>
+            })
+            .collect();
+
+        Ok(Arc::new(StreamingTableExec::try_new(
+            projected_schema,
+            partitions,
+            None,
+            vec![],
+            false,
+            limit,
+        )?))
+    }
+}
+
+struct IpcPartition {
+    file: IpcFile,
+    mmap: bool,
+    projection: Option<Vec<usize>>,
+    schema: SchemaRef,
+}
+
+impl PartitionStream for IpcPartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let mut builder = RecordBatchReceiverStream::builder(self.schema.clone(), 2);
+        let tx = builder.tx();
+        let file = self.file.clone();
+        let mmap = self.mmap;
+        let projection = self.projection.clone();
+
+        builder.spawn_blocking(move || {
+            let mut send = |batch: Result<RecordBatch, ArrowError>| {
+                // The receiver is gone once the query stops early, e.g. on LIMIT
+                tx.blocking_send(batch.map_err(DataFusionError::from)).is_ok()
+            };
+            let handle = File::open(&file.path)?;
+
+            match (file.format, mmap) {
+                (IpcFormat::File, true) => {
+                    let map = unsafe { Mmap::map(&handle)? };
+                    for batch in decode_mapped_file(map, projection)? {
+                        if !send(batch) {
+                            break;
+                        }
+                    }
+                }
+                (IpcFormat::Stream, true) => {
+                    let map = unsafe { Mmap::map(&handle)? };
+                    for batch in StreamReader::try_new(&map[..], projection)? {
+                        if !send(batch) {
+                            break;
+                        }
+                    }
+                }
+                (IpcFormat::File, false) => {
+                    for batch in FileReader::try_new(BufReader::new(handle), projection)? {
+                        if !send(batch) {
+                            break;
+                        }
+                    }
+                }
+                (IpcFormat::Stream, false) => {
+                    for batch in StreamReader::try_new(BufReader::new(handle), projection)? {
+                        if !send(batch) {
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        });
+
+        builder.build()
+    }
+}
+
+/// Decodes every record batch of a memory-mapped IPC file without copying
+/// the column buffers. Offsets read from the footer are checked against the
+/// file size, so a truncated or corrupt file is an error rather than a panic.
+fn decode_mapped_file(
+    map: Mmap,
+    projection: Option<Vec<usize>>,
+) -> Result<Vec<Result<RecordBatch, ArrowError>>, ArrowError> {
+    let len = map.len();
+    if len < 10 + FILE_MAGIC.len() {
+        return Err(ArrowError::IpcError("Arrow IPC file is too short".to_string()));
+    }
+    if &map[..FILE_MAGIC.len()] != FILE_MAGIC {
+        return Err(ArrowError::IpcError("Not an Arrow IPC file: missing ARROW1 magic".to_string()));
+    }
+    let ptr = NonNull::new(map.as_ptr() as *mut u8)
+        .ok_or_else(|| ArrowError::IpcError("Empty memory map".to_string()))?;
+    // SAFETY: the buffer keeps the read-only mapping alive for as long as any
+    // array references it
+    let buffer = unsafe { Buffer::from_custom_allocation(ptr, len, Arc::new(map)) };
+
+    let trailer_start = len - 10;
+    let footer_len = read_footer_length(buffer[trailer_start..].try_into().unwrap())?;
+    if footer_len > trailer_start {
+        return Err(ArrowError::IpcError("Arrow IPC footer length exceeds the file size".to_string()));
+    }
+    let footer = root_as_footer(&buffer[trailer_start - footer_len..trailer_start])
+        .map_err(|e| ArrowError::IpcError(format!("Invalid Arrow IPC footer: {}", e)))?;
+    let schema = footer
+        .schema()
+        .ok_or_else(|| ArrowError::IpcError("Arrow IPC footer has no schema".to_string()))?;
+
+    let mut decoder = FileDecoder::new(Arc::new(fb_to_schema(schema)), footer.version());
+    if let Some(projection) = projection {
+        decoder = decoder.with_projection(projection);
+    }
+
+    let block_data = |block: &Block| -> Result<Buffer, ArrowError> {
+        let out_of_bounds = || ArrowError::IpcError("Arrow IPC block lies outside the file".to_string());
+        let offset = usize::try_from(block.offset()).map_err(|_| out_of_bounds())?;
+        let body_len = usize::try_from(block.bodyLength()).map_err(|_| out_of_bounds())?;
+        let block_len = usize::try_from(block.metaDataLength())
+            .ok()
+            .and_then(|meta_len| meta_len.checked_add(body_len))
+            .ok_or_else(out_of_bounds)?;
+        match offset.checked_add(block_len) {
+            Some(end) if end <= len => Ok(buffer.slice_with_length(offset, block_len)),
+            _ => Err(out_of_bounds()),
+        }
+    };
+
+    for block in footer.dictionaries().iter().flatten() {
+        decoder.read_dictionary(block, &block_data(block)?)?;
+    }
+
+    let mut batches = Vec::new();
+    for block in footer.recordBatches().iter().flatten() {
+        let data = match block_data(block) {
+            Ok(data) => data,
+            Err(e) => {
+                batches.push(Err(e));
+                continue;
+            }
+        };
+        match decoder.read_record_batch(block, &data) {
+            Ok(Some(batch)) => batches.push(Ok(batch)),
+            Ok(None) => {}
+            Err(e) => batches.push(Err(e)),
+        }
+    }
+    Ok(batches)
+}
+
+/// Expands `location` as a glob pattern, or returns it as-is when it has no
+/// wildcards.
+fn resolve_paths(location: &str) -> AppResult<Vec<PathBuf>> {
+    let location = location.strip_prefix("file://").unwrap_or(location);
+    if !location.contains(['*', '?', '[']) {
+        return Ok(vec![PathBuf::from(location)]);
+    }
+
+    let mut paths = glob::glob(location)
+        .map_err(|e| AppError::ValidationError(format!("Invalid glob pattern {}: {}", location, e)))?
+        .filter_map(Result::ok)
+        .filter(|p| p.is_file())
+        .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Opens Arrow IPC files in place.
+///
+/// `connection_string` is a file path or glob pattern. `options["format"]`
+/// forces `file` (Feather v2) or `stream` instead of detecting it from the
+/// file header, and `options["mmap"] = "true"` memory-maps the files.
+pub fn load_tables(config: &DataSourceConfig) -> AppResult<SourceTables> {
+    let paths = resolve_paths(&config.connection_string)?;
+    let format = config.options.get("format").map(|f| IpcFormat::parse(f)).transpose()?;
+    let mmap = match config.options.get("mmap").map(String::as_str) {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(other) => {
+            return Err(AppError::ValidationError(format!("Invalid mmap option: {}", other)));
+        }
+    };
+
+    let table = ArrowIpcTable::try_new(paths, format, mmap)?;
+    Ok(SourceTables::Table(Arc::new(table)))
+}
+
+/// Detects `format` from the first matched file unless it is set.
+pub fn detect_options(config: &DataSourceConfig) -> AppResult<HashMap<String, String>> {
+    let mut detected = HashMap::new();
+    if config.options.contains_key("format") {
+        return Ok(detected);
+    }
+    if let Some(path) = resolve_paths(&config.connection_string)?.first() {
+        let format = match IpcFormat::detect(path)? {
+            IpcFormat::File => "file",
+            IpcFormat::Stream => "stream",
+        };
+        detected.insert("format".to_string(), format.to_string());
+    }
+    Ok(detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::data_source::DataSourceType;
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::ipc::writer::{FileWriter, StreamWriter};
+    use datafusion::prelude::SessionContext;
+
+    fn sample_batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_file_and_stream_glob() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let batch = sample_batch();
+
+        let mut writer = FileWriter::try_new(File::create(dir.join("a.arrow")).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let mut writer = StreamWriter::try_new(File::create(dir.join("b.arrow")).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(IpcFormat::detect(&dir.join("a.arrow")).unwrap(), IpcFormat::File);
+        assert_eq!(IpcFormat::detect(&dir.join("b.arrow")).unwrap(), IpcFormat::Stream);
+
+        for mmap in ["false", "true"] {
+            let config = DataSourceConfig::for_test(
+                DataSourceType::Arrow,
+                format!("{}/*.arrow", dir.display()),
+                HashMap::from([("mmap".to_string(), mmap.to_string())]),
+            );
+            let SourceTables::Table(table) = load_tables(&config).unwrap() else {
+                panic!("expected a single table");
+            };
+
+            let ctx = SessionContext::new();
+            ctx.register_table("events", table).unwrap();
+            let batches = ctx
+                .sql("SELECT sum(id) AS total FROM events WHERE name IS NOT NULL")
+                .await
+                .unwrap()
+                .collect()
+                .await
+                .unwrap();
+            let total = batches[0].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+            assert_eq!(total.value(0), 8);
+        }
+    }
+
+    #[test]
+    fn test_decode_mapped_file_rejects_corrupt_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let batch = sample_batch();
+        let map = |path: &Path| unsafe { Mmap::map(&File::open(path).unwrap()).unwrap() };
+
+        let mut writer = StreamWriter::try_new(File::create(dir.join("stream.arrow")).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        assert!(decode_mapped_file(map(&dir.join("stream.arrow")), None).is_err());
+
+        let mut writer = FileWriter::try_new(Vec::new(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let mut bytes = writer.into_inner().unwrap();
+        let trailer_start = bytes.len() - 10;
+        bytes[trailer_start..trailer_start + 4].copy_from_slice(&i32::MAX.to_le_bytes());
+        std::fs::write(dir.join("footer.arrow"), &bytes).unwrap();
+        assert!(decode_mapped_file(map(&dir.join("footer.arrow")), None).is_err());
+    }
+}
@@ -0,0 +1,568 @@
+use super::catalog::{validate_source_name, SharedCatalog};
+use super::query_engine::QueryEngine;
+use crate::services::materialized_view_repository::MaterializedViewRepository;
+use crate::utils::{AppError, AppResult};
+use arrow::datatypes::SchemaRef;
+use async_trait::async_trait;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result as DataFusionResult;
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::ExecutionPlan;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+
+/// Longest the scheduler sleeps, so new and changed schedules are picked up.
+const SCHEDULER_MAX_SLEEP: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// A query whose result is stored as Parquet and registered as a table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterializedView {
+    pub id: String,
+    pub name: String,
+    pub sql: String,
+    /// Cron expression for scheduled refreshes, see [`parse_schedule`].
+    pub schedule: Option<String>,
+    /// Directory holding the data of the latest successful refresh.
+    pub location: Option<String>,
+    pub last_refreshed_at: Option<chrono::NaiveDateTime>,
+    pub row_count: Option<u64>,
+    pub refresh_duration_ms: Option<u64>,
+    /// Error of the latest refresh if it failed; the previous data stays registered.
+    pub last_error: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Parses a cron expression: the standard five fields (`minute hour
+/// day-of-month month day-of-week`), or six with leading seconds.
+pub fn parse_schedule(expression: &str) -> AppResult<cron::Schedule> {
+    let expression = expression.trim();
+    let expression = match expression.split_whitespace().count() {
+        5 => format!("0 {}", expression),
+        _ => expression.to_string(),
+    };
+    cron::Schedule::from_str(&expression)
+        .map_err(|e| AppError::ValidationError(format!("Invalid schedule {:?}: {}", expression, e)))
+}
+
+/// Registered table of a materialized view. A refresh swaps the data it
+/// reads, so views planned on top of it see the latest refresh.
+struct MaterializedTable {
+    schema: SchemaRef,
+    data: std::sync::RwLock<Arc<dyn TableProvider>>,
+}
+
+impl MaterializedTable {
+    fn data(&self) -> Arc<dyn TableProvider> {
+        self.data.read().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl TableProvider for MaterializedTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        self.data().scan(state, projection, filters, limit).await
+    }
+
+    fn supports_filters_pushdown(&self, filters: &[&Expr]) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        self.data().supports_filters_pushdown(filters)
+    }
+}
+
+/// Held while a view is refreshed, updated or deleted.
+struct ViewLock<'a> {
+    refreshing: &'a Mutex<HashSet<String>>,
+    id: String,
+}
+
+impl Drop for ViewLock<'_> {
+    fn drop(&mut self) {
+        self.refreshing.lock().unwrap().remove(&self.id);
+    }
+}
+
+pub struct MaterializedViewManager {
+    views: RwLock<HashMap<String, MaterializedView>>,
+    /// Registered tables by view id.
+    tables: Mutex<HashMap<String, Arc<MaterializedTable>>>,
+    /// Views being refreshed, updated or deleted.
+    refreshing: Mutex<HashSet<String>>,
+    repository: Option<Arc<MaterializedViewRepository>>,
+    catalog: Arc<SharedCatalog>,
+    query_engine: Arc<QueryEngine>,
+    warehouse_dir: PathBuf,
+}
+
+impl MaterializedViewManager {
+    /// Creates a manager storing view data under `warehouse_dir`.
+    pub fn new(catalog: Arc<SharedCatalog>, query_engine: Arc<QueryEngine>, warehouse_dir: impl Into<PathBuf>) -> Self {
+        MaterializedViewManager {
+            views: RwLock::new(HashMap::new()),
+            tables: Mutex::new(HashMap::new()),
+            refreshing: Mutex::new(HashSet::new()),
+            repository: None,
+            catalog,
+            query_engine,
+            warehouse_dir: warehouse_dir.into(),
+        }
+    }
+
+    /// Persists materialized views through `repository`.
+    pub fn with_repository(self, repository: MaterializedViewRepository) -> Self {
+        MaterializedViewManager {
+            repository: Some(Arc::new(repository)),
+            ..self
+        }
+    }
+
+    /// Loads the persisted views and registers the data of their latest
+    /// refresh, refreshing the views that have none.
+    pub async fn restore_materialized_views(&self) -> AppResult<Vec<(String, AppError)>> {
+        let repository = match &self.repository {
+            Some(repository) => repository,
+            None => return Ok(vec![]),
+        };
+
+        let views = repository.list().await?;
+        {
+            let mut stored = self.views.write().await;
+            for view in &views {
+                stored.insert(view.id.clone(), view.clone());
+            }
+        }
+
+        let mut failures = Vec::new();
+        for view in views {
+            let restored = match &view.location {
+                Some(location) => self.register_location(&view, location).await,
+                None => Err(AppError::ValidationError("Not refreshed yet".to_string())),
+            };
+            if restored.is_err() {
+                if let Err(e) = self.refresh(&view.id).await {
+                    tracing::warn!("Failed to restore materialized view {} ({}): {}", view.name, view.id, e);
+                    failures.push((view.id, e));
+                }
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Stores a new view and runs its first refresh; a view whose query fails
+    /// is not stored.
+    pub async fn create_materialized_view(&self, view: MaterializedView) -> AppResult<MaterializedView> {
+        self.check_name(&view.name, None).await?;
+        if let Some(schedule) = &view.schedule {
+            parse_schedule(schedule)?;
+        }
+
+        let view = match self.materialize(view.clone()).await {
+            Ok(view) => view,
+            Err(e) => {
+                remove_dir(&self.warehouse_dir.join(&view.name));
+                return Err(e);
+            }
+        };
+        if let Some(repository) = &self.repository {
+            if let Err(e) = repository.insert(&view).await {
+                self.release(&view);
+                return Err(e);
+            }
+        }
+        self.views.write().await.insert(view.id.clone(), view.clone());
+        Ok(view)
+    }
+
+    pub async fn get_materialized_view(&self, id: &str) -> AppResult<MaterializedView> {
+        self.views
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| AppError::ValidationError(format!("Materialized view {} not found", id)))
+    }
+
+    pub async fn list_materialized_views(&self) -> AppResult<Vec<MaterializedView>> {
+        let mut views: Vec<MaterializedView> = self.views.read().await.values().cloned().collect();
+        views.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(views)
+    }
+
+    /// Replaces the name, query or schedule of a view and refreshes it. A
+    /// failed refresh leaves the view unchanged, and renaming is refused
+    /// while views read the old table.
+    pub async fn update_materialized_view(&self, id: &str, mut view: MaterializedView) -> AppResult<MaterializedView> {
+        let (_lock, previous) = self.lock_view(id).await?;
+        view.id = id.to_string();
+        view.created_at = previous.created_at;
+        view.location = previous.location.clone();
+        if let Some(schedule) = &view.schedule {
+            parse_schedule(schedule)?;
+        }
+        let renamed = previous.name != view.name;
+        if renamed {
+            self.check_name(&view.name, Some(id)).await?;
+            self.check_no_dependents(&previous.name, "renamed").await?;
+        }
+
+        // A renamed view gets a new table; the old one stays until the refresh succeeds
+        let old_table = match renamed {
+            true => self.tables.lock().unwrap().remove(id),
+            false => None,
+        };
+        let view = match self.materialize(view).await {
+            Ok(view) => view,
+            Err(e) => {
+                if let Some(table) = old_table {
+                    self.tables.lock().unwrap().insert(id.to_string(), table);
+                }
+                return Err(e);
+            }
+        };
+
+        if let Some(repository) = &self.repository {
+            repository.update(&view).await?;
+        }
+        self.views.write().await.insert(id.to_string(), view.clone());
+        if renamed {
+            self.catalog.deregister_source(&previous.name)?;
+            remove_dir(&self.warehouse_dir.join(&previous.name));
+        }
+        Ok(view)
+    }
+
+    /// Deletes a view, its table and its data. Refused while views read it
+    /// or while it is being refreshed.
+    pub async fn delete_materialized_view(&self, id: &str) -> AppResult<()> {
+        let (_lock, view) = self.lock_view(id).await?;
+        self.check_no_dependents(&view.name, "deleted").await?;
+
+        if let Some(repository) = &self.repository {
+            repository.delete(id).await?;
+        }
+        self.views.write().await.remove(id);
+        self.release(&view);
+        remove_dir(&self.warehouse_dir.join(&view.name));
+        Ok(())
+    }
+
+    /// Runs the view's query again and swaps in the result. A failed refresh
+    /// keeps the previous data and records the error.
+    pub async fn refresh(&self, id: &str) -> AppResult<MaterializedView> {
+        let (_lock, view) = self.lock_view(id).await?;
+        let (stored, result) = match self.materialize(view.clone()).await {
+            Ok(refreshed) => (refreshed.clone(), Ok(refreshed)),
+            Err(e) => {
+                let mut failed = view;
+                failed.last_error = Some(e.to_string());
+                failed.updated_at = chrono::Utc::now().naive_utc();
+                (failed, Err(e))
+            }
+        };
+        if let Some(repository) = &self.repository {
+            repository.update(&stored).await?;
+        }
+        self.views.write().await.insert(stored.id.clone(), stored);
+        result
+    }
+
+    /// Marks a view as busy until the returned lock is dropped, so it is not
+    /// refreshed, updated and deleted at once. The view is read after taking
+    /// the lock, so one deleted meanwhile is reported as not found.
+    async fn lock_view(&self, id: &str) -> AppResult<(ViewLock<'_>, MaterializedView)> {
+        let name = self.get_materialized_view(id).await?.name;
+        if !self.refreshing.lock().unwrap().insert(id.to_string()) {
+            return Err(AppError::ValidationError(format!(
+                "Materialized view {} is already being refreshed",
+                name
+            )));
+        }
+        let lock = ViewLock {
+            refreshing: &self.refreshing,
+            id: id.to_string(),
+        };
+        let view = self.get_materialized_view(id).await?;
+        Ok((lock, view))
+    }
+
+    /// Writes the query result into a new directory, registers it and
+    /// removes older data, keeping the previous refresh for running queries.
+    async fn materialize(&self, mut view: MaterializedView) -> AppResult<MaterializedView> {
+        let started = std::time::Instant::now();
+        let refreshed_at = chrono::Utc::now().naive_utc();
+        let view_dir = self.warehouse_dir.join(&view.name);
+        let location = view_dir.join(format!(
+            "{}-{}",
+            refreshed_at.format("%Y%m%d%H%M%S"),
+            uuid::Uuid::new_v4().simple()
+        ));
+        std::fs::create_dir_all(&location)
+            .map_err(|e| AppError::InternalError(format!("Cannot create {}: {}", location.display(), e)))?;
+        let location = format!("{}/", location.display());
+
+        let installed = async {
+            let (schema, rows) = self.query_engine.write_parquet(&view.sql, &location).await?;
+            self.install(&view, schema, &location).await?;
+            Ok::<u64, AppError>(rows)
+        }
+        .await;
+        let rows = match installed {
+            Ok(rows) => rows,
+            Err(e) => {
+                remove_dir(Path::new(&location));
+                return Err(e);
+            }
+        };
+
+        let previous = view.location.replace(location.clone());
+        prune_generations(&view_dir, &[Some(location.as_str()), previous.as_deref()]);
+        view.last_refreshed_at = Some(refreshed_at);
+        view.row_count = Some(rows);
+        view.refresh_duration_ms = Some(started.elapsed().as_millis() as u64);
+        view.last_error = None;
+        view.updated_at = refreshed_at;
+        Ok(view)
+    }
+
+    /// Registers the data of a previous refresh, as on startup.
+    async fn register_location(&self, view: &MaterializedView, location: &str) -> AppResult<()> {
+        let url = ListingTableUrl::parse(location)?;
+        let schema = parquet_options()
+            .infer_schema(&self.catalog.session_context().state(), &url)
+            .await?;
+        self.install(view, schema, location).await
+    }
+
+    /// Points the view's table at the Parquet files in `location`. The table
+    /// is replaced when the schema changes, which views reading it forbid.
+    async fn install(&self, view: &MaterializedView, schema: SchemaRef, location: &str) -> AppResult<()> {
+        let config = ListingTableConfig::new(ListingTableUrl::parse(location)?)
+            .with_listing_options(parquet_options())
+            .with_schema(schema.clone());
+        let data: Arc<dyn TableProvider> = Arc::new(ListingTable::try_new(config)?);
+
+        let current = self.tables.lock().unwrap().get(&view.id).cloned();
+        if let Some(table) = current.filter(|table| table.schema.fields() == schema.fields()) {
+            *table.data.write().unwrap() = data;
+            return Ok(());
+        }
+        if self.tables.lock().unwrap().contains_key(&view.id) {
+            self.check_no_dependents(&view.name, "given a new schema").await?;
+        }
+
+        let table = Arc::new(MaterializedTable {
+            schema,
+            data: std::sync::RwLock::new(data),
+        });
+        self.catalog.register_table(&view.name, table.clone())?;
+        self.tables.lock().unwrap().insert(view.id.clone(), table);
+        Ok(())
+    }
+
+    /// Removes the registered table of a view.
+    fn release(&self, view: &MaterializedView) {
+        self.tables.lock().unwrap().remove(&view.id);
+        if let Err(e) = self.catalog.deregister_source(&view.name) {
+            tracing::warn!("Failed to deregister materialized view {}: {}", view.name, e);
+        }
+    }
+
+    async fn check_name(&self, name: &str, owner: Option<&str>) -> AppResult<()> {
+        validate_source_name(name)?;
+        let taken = self
+            .views
+            .read()
+            .await
+            .values()
+            .any(|other| other.name == name && Some(other.id.as_str()) != owner);
+        if taken || self.catalog.is_registered(name)? {
+            return Err(AppError::ValidationError(format!(
+                "The name {} is already used by another data source, table or view",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    async fn check_no_dependents(&self, name: &str, action: &str) -> AppResult<()> {
+        let views = self.catalog.dependent_views(name).await?;
+        if views.is_empty() {
+            return Ok(());
+        }
+        let views: Vec<String> = views.iter().map(|v| v.to_string()).collect();
+        Err(AppError::ValidationError(format!(
+            "Materialized view {} cannot be {} while views read it: {}",
+            name,
+            action,
+            views.join(", ")
+        )))
+    }
+
+    /// Refreshes the views whose schedule is due, starting with the next run
+    /// after their last refresh (or creation).
+    pub fn spawn_scheduler(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut next_runs: HashMap<String, chrono::DateTime<chrono::Utc>> = HashMap::new();
+            loop {
+                let now = chrono::Utc::now();
+                let views: Vec<MaterializedView> = self.views.read().await.values().cloned().collect();
+                next_runs.retain(|id, _| views.iter().any(|view| view.id == *id));
+
+                for view in views {
+                    let Some(schedule) = view.schedule.as_deref().and_then(|s| parse_schedule(s).ok()) else {
+                        next_runs.remove(&view.id);
+                        continue;
+                    };
+                    let since = view.last_refreshed_at.unwrap_or(view.created_at).and_utc();
+                    let next = *next_runs
+                        .entry(view.id.clone())
+                        .or_insert_with(|| schedule.after(&since).next().unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC));
+                    if next <= now {
+                        if let Err(e) = self.refresh(&view.id).await {
+                            tracing::warn!("Scheduled refresh of materialized view {} failed: {}", view.name, e);
+                        }
+                        let after = chrono::Utc::now();
+                        if let Some(next) = schedule.after(&after).next() {
+                            next_runs.insert(view.id.clone(), next);
+                        }
+                    }
+                }
+
+                let sleep = next_runs
+                    .values()
+                    .filter_map(|next| (*next - chrono::Utc::now()).to_std().ok())
+                    .min()
+                    .unwrap_or(SCHEDULER_MAX_SLEEP)
+                    .min(SCHEDULER_MAX_SLEEP);
+                tokio::time::sleep(sleep.max(std::time::Duration::from_secs(1))).await;
+            }
+        })
+    }
+}
+
+fn parquet_options() -> ListingOptions {
+    ListingOptions::new(Arc::new(ParquetFormat::default())).with_file_extension(".parquet")
+}
+
+/// Removes the refresh directories of a view other than `keep`.
+fn prune_generations(view_dir: &Path, keep: &[Option<&str>]) {
+    let Ok(entries) = std::fs::read_dir(view_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = format!("{}/", entry.path().display());
+        if !keep.iter().flatten().any(|kept| *kept == path) {
+            remove_dir(&entry.path());
+        }
+    }
+}
+
+fn remove_dir(path: &Path) {
+    if let Err(e) = std::fs::remove_dir_all(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafusion_adapters::test_utils::count;
+
+    fn materialized_view(name: &str, sql: &str) -> MaterializedView {
+        let now = chrono::Utc::now().naive_utc();
+        MaterializedView {
+            id: name.to_string(),
+            name: name.to_string(),
+            sql: sql.to_string(),
+            schedule: None,
+            location: None,
+            last_refreshed_at: None,
+            row_count: None,
+            refresh_duration_ms: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        assert!(parse_schedule("*/15 * * * *").is_ok());
+        assert!(parse_schedule("0 30 2 * * Mon").is_ok());
+        assert!(parse_schedule("every hour").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_swaps_data() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let catalog = Arc::new(SharedCatalog::new());
+        let engine = Arc::new(QueryEngine::new(catalog.clone()));
+        let manager = MaterializedViewManager::new(catalog.clone(), engine, dir);
+        let ctx = catalog.session_context();
+        ctx.sql("CREATE TABLE orders (id BIGINT, amount DOUBLE) AS VALUES (1, 2.5), (2, 4.0)")
+            .await
+            .unwrap();
+
+        assert!(manager
+            .create_materialized_view(materialized_view("broken", "SELECT missing FROM orders"))
+            .await
+            .is_err());
+        assert!(manager.get_materialized_view("broken").await.is_err());
+
+        let view = manager
+            .create_materialized_view(materialized_view("order_totals", "SELECT id, sum(amount) AS total FROM orders GROUP BY id"))
+            .await
+            .unwrap();
+        assert_eq!(view.row_count, Some(2));
+        assert!(view.last_refreshed_at.is_some());
+        ctx.sql("CREATE VIEW big_totals AS SELECT id FROM order_totals WHERE total > 3")
+            .await
+            .unwrap();
+
+        ctx.sql("INSERT INTO orders VALUES (3, 10.0)").await.unwrap().collect().await.unwrap();
+        assert_eq!(count(&ctx, "SELECT count(*) FROM order_totals").await, 2);
+        let view = manager.refresh("order_totals").await.unwrap();
+        assert_eq!(view.row_count, Some(3));
+        assert_eq!(count(&ctx, "SELECT count(*) FROM big_totals").await, 2);
+
+        // Views reading it block the deletion
+        assert!(manager.delete_materialized_view("order_totals").await.is_err());
+        ctx.sql("DROP VIEW big_totals").await.unwrap();
+        // So does a refresh in progress, which would otherwise register it again
+        let (refreshing, _) = manager.lock_view("order_totals").await.unwrap();
+        assert!(manager.delete_materialized_view("order_totals").await.is_err());
+        assert!(manager.refresh("order_totals").await.is_err());
+        drop(refreshing);
+        manager.delete_materialized_view("order_totals").await.unwrap();
+        assert!(manager.refresh("order_totals").await.is_err());
+        assert!(!dir.join("order_totals").exists());
+    }
+}
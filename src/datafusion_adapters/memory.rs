@@ -0,0 +1,326 @@
+use super::catalog::{SharedCatalog, SourceTables};
+use crate::utils::{AppError, AppResult};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use datafusion::datasource::MemTable;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+const JSON_BATCH_SIZE: usize = 1024;
+
+/// Column of a memory table, as given when the table is created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDefinition {
+    pub name: String,
+    /// Arrow type name, e.g. `Int64`, `Utf8` or `Timestamp(Microsecond, None)`.
+    pub data_type: String,
+    #[serde(default = "default_nullable")]
+    pub nullable: bool,
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+impl From<&Field> for ColumnDefinition {
+    fn from(field: &Field) -> Self {
+        ColumnDefinition {
+            name: field.name().clone(),
+            data_type: field.data_type().to_string(),
+            nullable: field.is_nullable(),
+        }
+    }
+}
+
+pub fn schema_to_columns(schema: &Schema) -> Vec<ColumnDefinition> {
+    schema.fields().iter().map(|f| f.as_ref().into()).collect()
+}
+
+pub fn columns_to_schema(columns: &[ColumnDefinition]) -> AppResult<SchemaRef> {
+    if columns.is_empty() {
+        return Err(AppError::ValidationError(
+            "A column schema needs at least one column".to_string(),
+        ));
+    }
+    let fields = columns
+        .iter()
+        .map(|c| {
+            let data_type: DataType = c.data_type.parse().map_err(|_| {
+                AppError::ValidationError(format!("Unknown type {} for column {}", c.data_type, c.name))
+            })?;
+            Ok(Field::new(&c.name, data_type, c.nullable))
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// Encodings accepted when appending rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowFormat {
+    /// A JSON array of objects, or newline-delimited objects.
+    Json,
+    /// CSV with a header row.
+    Csv,
+    /// Arrow IPC stream or file.
+    ArrowIpc,
+}
+
+impl RowFormat {
+    pub fn from_content_type(content_type: &str) -> AppResult<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim();
+        match mime {
+            "application/json" | "application/x-ndjson" => Ok(RowFormat::Json),
+            "text/csv" => Ok(RowFormat::Csv),
+            "application/vnd.apache.arrow.stream" | "application/vnd.apache.arrow.file" => {
+                Ok(RowFormat::ArrowIpc)
+            }
+            other => Err(AppError::ValidationError(format!(
+                "Unsupported content type for rows: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Decodes a request body into batches of `schema`.
+pub fn decode_rows(schema: SchemaRef, format: RowFormat, body: &[u8]) -> AppResult<Vec<RecordBatch>> {
+    let invalid = |e: arrow::error::ArrowError| AppError::ValidationError(format!("Invalid rows: {}", e));
+    match format {
+        RowFormat::Json => {
+            let mut decoder = arrow::json::ReaderBuilder::new(schema)
+                .with_batch_size(JSON_BATCH_SIZE)
+                .build_decoder()
+                .map_err(invalid)?;
+            let mut batches = Vec::new();
+            match serde_json::from_slice::<Vec<serde_json::Value>>(body) {
+                Ok(rows) => {
+                    for chunk in rows.chunks(JSON_BATCH_SIZE) {
+                        decoder.serialize(chunk).map_err(invalid)?;
+                        batches.extend(decoder.flush().map_err(invalid)?);
+                    }
+                }
+                Err(_) => {
+                    // Newline-delimited JSON; the decoder stops at each full batch
+                    let mut remaining = body;
+                    while !remaining.is_empty() {
+                        let read = decoder.decode(remaining).map_err(invalid)?;
+                        remaining = &remaining[read..];
+                        batches.extend(decoder.flush().map_err(invalid)?);
+                    }
+                }
+            }
+            batches.extend(decoder.flush().map_err(invalid)?);
+            Ok(batches)
+        }
+        RowFormat::Csv => arrow::csv::ReaderBuilder::new(schema)
+            .with_header(true)
+            .build(Cursor::new(body))
+            .map_err(invalid)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid),
+        RowFormat::ArrowIpc => {
+            let batches = if body.starts_with(b"ARROW1") {
+                arrow::ipc::reader::FileReader::try_new(Cursor::new(body), None)
+                    .map_err(invalid)?
+                    .collect::<Result<Vec<_>, _>>()
+            } else {
+                arrow::ipc::reader::StreamReader::try_new(Cursor::new(body), None)
+                    .map_err(invalid)?
+                    .collect::<Result<Vec<_>, _>>()
+            }
+            .map_err(invalid)?;
+
+            // Accept matching columns even if field metadata differs
+            batches
+                .into_iter()
+                .map(|batch| {
+                    if batch.schema().fields() != schema.fields() {
+                        return Err(AppError::ValidationError(
+                            "Arrow IPC schema does not match the table schema".to_string(),
+                        ));
+                    }
+                    RecordBatch::try_new(schema.clone(), batch.columns().to_vec()).map_err(invalid)
+                })
+                .collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct MemoryTableSize {
+    pub rows: usize,
+    pub bytes: usize,
+}
+
+struct MemoryTableData {
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+    size: MemoryTableSize,
+}
+
+/// Memory tables filled through the API, with a cap on their combined size.
+///
+/// Every change registers a fresh `MemTable` snapshot in the shared catalog,
+/// so queries already running keep reading the rows they started with.
+pub struct MemoryTables {
+    catalog: Arc<SharedCatalog>,
+    tables: RwLock<HashMap<String, MemoryTableData>>,
+    limit: usize,
+}
+
+impl MemoryTables {
+    pub fn new(catalog: Arc<SharedCatalog>, limit: usize) -> Self {
+        MemoryTables {
+            catalog,
+            tables: RwLock::new(HashMap::new()),
+            limit,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Creates an empty table, or keeps the rows of an existing table with the same schema.
+    pub async fn create(&self, name: &str, schema: SchemaRef) -> AppResult<()> {
+        let mut tables = self.tables.write().await;
+        let data = match tables.remove(name) {
+            Some(existing) if existing.schema.fields() == schema.fields() => existing,
+            _ => MemoryTableData {
+                schema,
+                batches: vec![],
+                size: MemoryTableSize::default(),
+            },
+        };
+        self.publish(name, &data)?;
+        tables.insert(name.to_string(), data);
+        Ok(())
+    }
+
+    pub async fn schema(&self, name: &str) -> AppResult<SchemaRef> {
+        let tables = self.tables.read().await;
+        tables
+            .get(name)
+            .map(|t| t.schema.clone())
+            .ok_or_else(|| AppError::ValidationError(format!("Memory table {} not found", name)))
+    }
+
+    pub async fn append(&self, name: &str, batches: Vec<RecordBatch>) -> AppResult<MemoryTableSize> {
+        let mut tables = self.tables.write().await;
+        let used: usize = tables.values().map(|t| t.size.bytes).sum();
+        let data = tables
+            .get_mut(name)
+            .ok_or_else(|| AppError::ValidationError(format!("Memory table {} not found", name)))?;
+
+        let added_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        let added_bytes: usize = batches.iter().map(|b| b.get_array_memory_size()).sum();
+        if used + added_bytes > self.limit {
+            return Err(AppError::ValidationError(format!(
+                "Appending {} bytes would exceed the memory table limit of {} bytes ({} in use)",
+                added_bytes, self.limit, used
+            )));
+        }
+
+        data.batches.extend(batches.into_iter().filter(|b| b.num_rows() > 0));
+        data.size.rows += added_rows;
+        data.size.bytes += added_bytes;
+        self.publish(name, data)?;
+        Ok(data.size)
+    }
+
+    pub async fn truncate(&self, name: &str) -> AppResult<()> {
+        let mut tables = self.tables.write().await;
+        let data = tables
+            .get_mut(name)
+            .ok_or_else(|| AppError::ValidationError(format!("Memory table {} not found", name)))?;
+        data.batches.clear();
+        data.size = MemoryTableSize::default();
+        self.publish(name, data)
+    }
+
+    /// Forgets a table's rows; the caller is responsible for the catalog entry.
+    pub async fn remove(&self, name: &str) {
+        self.tables.write().await.remove(name);
+    }
+
+    pub async fn size(&self, name: &str) -> Option<MemoryTableSize> {
+        self.tables.read().await.get(name).map(|t| t.size)
+    }
+
+    fn publish(&self, name: &str, data: &MemoryTableData) -> AppResult<()> {
+        let table = MemTable::try_new(data.schema.clone(), vec![data.batches.clone()])?;
+        self.catalog.register_source(name, SourceTables::Table(Arc::new(table)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<ColumnDefinition> {
+        vec![
+            ColumnDefinition {
+                name: "id".to_string(),
+                data_type: "Int64".to_string(),
+                nullable: false,
+            },
+            ColumnDefinition {
+                name: "name".to_string(),
+                data_type: "Utf8".to_string(),
+                nullable: true,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_decode_json_and_csv() {
+        let schema = columns_to_schema(&columns()).unwrap();
+
+        let json = br#"[{"id": 1, "name": "a"}, {"id": 2}]"#;
+        let batches = decode_rows(schema.clone(), RowFormat::Json, json).unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+
+        let csv = b"id,name\n3,c\n4,d\n5,\n";
+        let batches = decode_rows(schema.clone(), RowFormat::Csv, csv).unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+        assert!(decode_rows(schema.clone(), RowFormat::Json, br#"[{"id": "x"}]"#).is_err());
+
+        let round_trip = columns_to_schema(&schema_to_columns(&schema)).unwrap();
+        assert_eq!(round_trip.fields(), schema.fields());
+    }
+
+    #[tokio::test]
+    async fn test_append_truncate_and_limit() {
+        let catalog = Arc::new(SharedCatalog::new());
+        let tables = MemoryTables::new(catalog.clone(), 4096);
+        let schema = columns_to_schema(&columns()).unwrap();
+        tables.create("scratch", schema.clone()).await.unwrap();
+
+        let batches = decode_rows(schema.clone(), RowFormat::Json, br#"[{"id": 1}, {"id": 2}]"#).unwrap();
+        let size = tables.append("scratch", batches).await.unwrap();
+        assert_eq!(size.rows, 2);
+
+        let batches = catalog
+            .session_context()
+            .sql("SELECT count(*) FROM scratch")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let count = batches[0].column(0).as_any().downcast_ref::<arrow::array::Int64Array>().unwrap();
+        assert_eq!(count.value(0), 2);
+
+        let rows: Vec<String> = (0..10_000).map(|i| format!("{{\"id\": {}}}", i)).collect();
+        let big = decode_rows(schema, RowFormat::Json, rows.join("\n").as_bytes()).unwrap();
+        assert!(tables.append("scratch", big).await.is_err());
+
+        tables.truncate("scratch").await.unwrap();
+        assert_eq!(tables.size("scratch").await.unwrap(), MemoryTableSize::default());
+    }
+}
@@ -1,7 +1,28 @@
+pub mod adbc_source;
+pub mod catalog;
 pub mod data_source;
+pub mod delta;
+pub mod files;
+pub mod mysql;
+pub mod object_stores;
+pub mod postgres;
+pub mod remote;
+pub mod rest;
 pub mod query_engine;
 pub mod flight_server;
+pub mod iceberg;
+pub mod ipc;
+pub mod materialized_view;
+pub mod memory;
+pub mod sql_pushdown;
+pub mod sqlite;
+pub mod statistics;
+#[cfg(test)]
+mod test_utils;
+pub mod view;
+pub mod watch;
 
+pub use catalog::*;
 pub use data_source::*;
 pub use query_engine::*;
 pub use flight_server::*;
\ No newline at end of file
@@ -0,0 +1,286 @@
+use crate::utils::{AppError, AppResult};
+use datafusion::execution::runtime_env::RuntimeEnv;
+use object_store::aws::AmazonS3Builder;
+use object_store::http::HttpBuilder;
+use object_store::ObjectStore;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use url::{Host, Url};
+
+/// Data source options that configure object store access rather than the reader.
+pub const OBJECT_STORE_OPTIONS: &[&str] = &[
+    "access_key_id",
+    "secret_access_key",
+    "session_token",
+    "region",
+    "endpoint",
+    "allow_http",
+    "virtual_hosted_style",
+    "skip_signature",
+];
+
+fn parse_bool(options: &HashMap<String, String>, key: &str) -> AppResult<Option<bool>> {
+    options
+        .get(key)
+        .map(|v| match v.as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            other => Err(AppError::ValidationError(format!("Invalid {} option: {}", key, other))),
+        })
+        .transpose()
+}
+
+/// Builds an S3 store for `bucket` from `options` alone. The server's own
+/// `AWS_*` variables and instance role are never used, so a bucket is only
+/// read with the credentials given for it, or anonymously with `skip_signature`.
+fn s3_store(bucket: &str, options: &HashMap<String, String>) -> AppResult<Arc<dyn ObjectStore>> {
+    let has_keys = options.contains_key("access_key_id") && options.contains_key("secret_access_key");
+    if !has_keys && parse_bool(options, "skip_signature")? != Some(true) {
+        return Err(AppError::ValidationError(
+            "S3 locations need access_key_id and secret_access_key, or skip_signature=true for public buckets"
+                .to_string(),
+        ));
+    }
+    let mut builder = AmazonS3Builder::new().with_bucket_name(bucket);
+    if let Some(value) = options.get("access_key_id") {
+        builder = builder.with_access_key_id(value);
+    }
+    if let Some(value) = options.get("secret_access_key") {
+        builder = builder.with_secret_access_key(value);
+    }
+    if let Some(value) = options.get("session_token") {
+        builder = builder.with_token(value);
+    }
+    if let Some(value) = options.get("region") {
+        builder = builder.with_region(value);
+    }
+    if let Some(endpoint) = options.get("endpoint") {
+        // S3-compatible stores such as MinIO are usually addressed by path
+        // and often run without TLS
+        builder = builder
+            .with_endpoint(endpoint)
+            .with_allow_http(endpoint.starts_with("http://"))
+            .with_virtual_hosted_style_request(false);
+    }
+    if let Some(value) = parse_bool(options, "allow_http")? {
+        builder = builder.with_allow_http(value);
+    }
+    if let Some(value) = parse_bool(options, "virtual_hosted_style")? {
+        builder = builder.with_virtual_hosted_style_request(value);
+    }
+    if let Some(value) = parse_bool(options, "skip_signature")? {
+        builder = builder.with_skip_signature(value);
+    }
+
+    let store = builder
+        .build()
+        .map_err(|e| AppError::ValidationError(format!("Invalid S3 configuration: {}", e)))?;
+    Ok(Arc::new(store))
+}
+
+fn http_store(base_url: &Url) -> AppResult<Arc<dyn ObjectStore>> {
+    let store = HttpBuilder::new()
+        .with_url(base_url.as_str())
+        .build()
+        .map_err(|e| AppError::ValidationError(format!("Invalid HTTP store {}: {}", base_url, e)))?;
+    Ok(Arc::new(store))
+}
+
+/// Whether `ip` is reachable from the internet, as opposed to loopback,
+/// private, link-local (such as the cloud metadata endpoint) and similar ranges.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Rejects HTTP locations on hosts that resolve to non-public addresses, so
+/// a data source cannot make the server read from its own network.
+///
+/// This is best-effort: the object store resolves the host again for every
+/// request and follows redirects, and `HttpBuilder` offers no way to pin the
+/// checked addresses or turn redirects off. A host that changes its DNS
+/// answer or redirects to an internal address gets past the check, so
+/// deployments that must not reach internal services need egress filtering
+/// at the network level as well.
+async fn check_public_host(url: &Url) -> AppResult<()> {
+    let refused = || AppError::ValidationError(format!("HTTP locations must be on a public host: {}", url));
+    let addresses: Vec<IpAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| AppError::ValidationError(format!("Cannot resolve {}: {}", domain, e)))?
+                .map(|address| address.ip())
+                .collect()
+        }
+        None => return Err(refused()),
+    };
+    if addresses.is_empty() || !addresses.into_iter().all(is_public_ip) {
+        return Err(refused());
+    }
+    Ok(())
+}
+
+/// A DNS label made of `value`, which may hold any characters.
+fn label(prefix: char, value: &str) -> String {
+    std::iter::once(prefix.to_string())
+        .chain(value.bytes().map(|b| format!("{:02x}", b)))
+        .collect()
+}
+
+/// `location` rewritten to the store a data source registered for it with
+/// [`register_object_store`]. The host becomes a key naming both the source
+/// (`scope`) and the original bucket or host; object paths are unchanged.
+/// Local paths and `file://` URLs are returned as they are.
+pub fn scoped_location(scope: &str, location: &str) -> AppResult<String> {
+    let mut url = match Url::parse(location) {
+        Ok(url) if matches!(url.scheme(), "s3" | "s3a" | "http" | "https") => url,
+        _ => return Ok(location.to_string()),
+    };
+    let authority = url[url::Position::BeforeHost..url::Position::AfterPort].to_string();
+    let host = format!("{}.{}", label('s', scope), label('h', &authority));
+    let invalid = || AppError::ValidationError(format!("Invalid location {}", location));
+    url.set_port(None).map_err(|_| invalid())?;
+    url.set_host(Some(&host)).map_err(|_| invalid())?;
+    Ok(url.to_string())
+}
+
+/// Registers the object store that serves `location` for the data source
+/// `scope` (its id) and returns the location to read it through.
+///
+/// `s3://`/`s3a://` and `http(s)://` locations get a store per source and
+/// bucket or host, configured from the data source `options`, so sources
+/// reading the same bucket with different credentials do not share a store.
+/// HTTP locations must be on public hosts, as far as
+/// [`check_public_host`] can tell. Local paths and `file://` URLs use
+/// the runtime's built-in local file system store.
+pub async fn register_object_store(
+    runtime: &RuntimeEnv,
+    scope: &str,
+    location: &str,
+    options: &HashMap<String, String>,
+) -> AppResult<String> {
+    let url = match Url::parse(location) {
+        Ok(url) if url.scheme().len() > 1 => url,
+        // Plain paths (a one-letter "scheme" is a Windows drive letter)
+        _ => return Ok(location.to_string()),
+    };
+
+    let host = url.host_str().unwrap_or_default();
+    let store = match url.scheme() {
+        "file" => return Ok(location.to_string()),
+        "s3" | "s3a" => {
+            if host.is_empty() {
+                return Err(AppError::ValidationError(format!("Missing bucket in {}", location)));
+            }
+            s3_store(host, options)?
+        }
+        "http" | "https" => {
+            check_public_host(&url).await?;
+            let base_url = Url::parse(&url.origin().ascii_serialization())
+                .map_err(|e| AppError::ValidationError(format!("Invalid location {}: {}", location, e)))?;
+            http_store(&base_url)?
+        }
+        other => {
+            return Err(AppError::ValidationError(format!(
+                "Unsupported location scheme: {}",
+                other
+            )))
+        }
+    };
+
+    let scoped = scoped_location(scope, location)?;
+    let store_url = Url::parse(&scoped)
+        .map_err(|e| AppError::ValidationError(format!("Invalid location {}: {}", location, e)))?;
+    runtime.register_object_store(&store_url, store);
+    Ok(scoped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_register_object_store() {
+        let runtime = RuntimeEnv::default();
+        let minio = options(&[
+            ("endpoint", "http://localhost:9000"),
+            ("access_key_id", "minio"),
+            ("secret_access_key", "minio123"),
+            ("region", "us-east-1"),
+        ]);
+
+        let location = register_object_store(&runtime, "a", "s3://warehouse/events/", &minio).await.unwrap();
+        assert!(location.ends_with("/events/"));
+        assert!(runtime.object_store(Url::parse(&location).unwrap()).is_ok());
+        assert!(runtime.object_store(Url::parse("s3://warehouse").unwrap()).is_err());
+
+        // Each source gets its own store for the same bucket
+        let public = options(&[("skip_signature", "true")]);
+        let other = register_object_store(&runtime, "b", "s3://warehouse/events/", &public).await.unwrap();
+        assert_ne!(Url::parse(&location).unwrap().host(), Url::parse(&other).unwrap().host());
+
+        let location = register_object_store(&runtime, "a", "https://93.184.215.14/data/file.parquet", &HashMap::new())
+            .await
+            .unwrap();
+        assert!(location.ends_with("/data/file.parquet"));
+        assert!(runtime.object_store(Url::parse(&location).unwrap()).is_ok());
+
+        let local = register_object_store(&runtime, "a", "/var/data/file.csv", &HashMap::new()).await.unwrap();
+        assert_eq!(local, "/var/data/file.csv");
+        assert!(register_object_store(&runtime, "a", "ftp://example.com/file.csv", &HashMap::new()).await.is_err());
+
+        let invalid = options(&[("skip_signature", "yes")]);
+        assert!(register_object_store(&runtime, "a", "s3://warehouse/", &invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_server_credentials_and_network_are_not_used() {
+        let runtime = RuntimeEnv::default();
+        // No credentials: the server's environment and role are not tried
+        assert!(register_object_store(&runtime, "a", "s3://warehouse/", &HashMap::new()).await.is_err());
+
+        for location in [
+            "http://127.0.0.1:8080/data.csv",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.5/data.csv",
+            "http://[::1]/data.csv",
+            "http://localhost/data.csv",
+        ] {
+            assert!(
+                register_object_store(&runtime, "a", location, &HashMap::new()).await.is_err(),
+                "{}",
+                location
+            );
+        }
+    }
+}
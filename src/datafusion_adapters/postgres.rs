@@ -0,0 +1,418 @@
+use super::catalog::SourceTables;
+use super::data_source::DataSourceConfig;
+use super::sql_pushdown::{
+    build_select, columns_to_schema, filters_pushdown, parse_decimal, PostgresDialect, RemoteColumn,
+    SelectedColumns, SqlDialect, EPOCH_DAYS_FROM_CE, FETCH_BATCH_SIZE,
+};
+use crate::utils::{AppError, AppResult};
+use arrow::array::*;
+use arrow::datatypes::{DataType, SchemaRef, TimeUnit};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use async_trait::async_trait;
+use chrono::{Datelike, Timelike};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::SessionState;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::streaming::{PartitionStream, StreamingTableExec};
+use datafusion::physical_plan::ExecutionPlan;
+use futures::{StreamExt, TryStreamExt};
+use sqlx::postgres::{PgConnection, PgPool, PgPoolOptions, PgRow, Postgres};
+use sqlx::{Connection, Row};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+
+/// A single PostgreSQL table or view exposed to DataFusion.
+///
+/// Projections, translatable filters and limits are rendered into the remote
+/// `SELECT`, so only the requested rows and columns leave the database.
+pub struct PostgresTable {
+    pool: PgPool,
+    table: String,
+    columns: Vec<RemoteColumn>,
+    schema: SchemaRef,
+}
+
+impl PostgresTable {
+    pub async fn try_new(pool: PgPool, schema_name: &str, table_name: &str) -> AppResult<Self> {
+        let columns = introspect_columns(&pool, schema_name, table_name).await?;
+        if columns.is_empty() {
+            return Err(AppError::ValidationError(format!(
+                "Table {}.{} not found or has no columns",
+                schema_name, table_name
+            )));
+        }
+
+        let dialect = PostgresDialect;
+        Ok(PostgresTable {
+            pool,
+            table: format!(
+                "{}.{}",
+                dialect.quote_identifier(schema_name),
+                dialect.quote_identifier(table_name)
+            ),
+            schema: columns_to_schema(&columns),
+            columns,
+        })
+    }
+
+    /// Filters read cast columns through the same cast as the projection,
+    /// except decimals, which compare against the raw numeric column.
+    fn dialect(&self) -> SelectedColumns<'_> {
+        SelectedColumns {
+            dialect: &PostgresDialect,
+            columns: &self.columns,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for PostgresTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters_pushdown(&self.dialect(), &self.schema, filters))
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let columns: Vec<&RemoteColumn> = match projection {
+            Some(indices) => indices.iter().map(|i| &self.columns[*i]).collect(),
+            None => self.columns.iter().collect(),
+        };
+        let projected_schema = match projection {
+            Some(indices) => Arc::new(self.schema.project(indices)?),
+            None => self.schema.clone(),
+        };
+
+        let sql = build_select(&self.dialect(), &self.table, &columns, filters, limit);
+        tracing::debug!("PostgreSQL pushdown query: {}", sql);
+
+        let partition = PostgresPartition {
+            pool: self.pool.clone(),
+            sql,
+            schema: projected_schema.clone(),
+        };
+        Ok(Arc::new(StreamingTableExec::try_new(
+            projected_schema,
+            vec![Arc::new(partition)],
+            None,
+            vec![],
+            false,
+            None,
+        )?))
+    }
+}
+
+struct PostgresPartition {
+    pool: PgPool,
+    sql: String,
+    schema: SchemaRef,
+}
+
+impl PartitionStream for PostgresPartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let mut builder = RecordBatchReceiverStream::builder(self.schema.clone(), 2);
+        let tx = builder.tx();
+        let pool = self.pool.clone();
+        let sql = self.sql.clone();
+        let schema = self.schema.clone();
+
+        builder.spawn(async move {
+            let mut chunks = sqlx::query(&sql).fetch(&pool).try_chunks(FETCH_BATCH_SIZE);
+            while let Some(rows) = chunks.next().await {
+                let rows = rows.map_err(|e| DataFusionError::External(Box::new(e.1)))?;
+                // The receiver is gone once the query stops early, e.g. on LIMIT
+                if tx.send(rows_to_batch(&rows, &schema)).await.is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        builder.build()
+    }
+}
+
+/// Checks that the database accepts a connection and answers `SELECT 1`.
+pub async fn ping(config: &DataSourceConfig) -> AppResult<()> {
+    let mut connection = PgConnection::connect(&config.connection_string).await?;
+    sqlx::query("SELECT 1").execute(&mut connection).await?;
+    connection.close().await?;
+    Ok(())
+}
+
+/// Opens a PostgreSQL data source.
+///
+/// `options["table"]` (`table` or `schema.table`) exposes a single table,
+/// `options["schema"]` exposes every table of one schema, and without either
+/// every user schema of the database is exposed.
+pub async fn load_tables(config: &DataSourceConfig) -> AppResult<SourceTables> {
+    let max_connections = match config.options.get("max_connections") {
+        Some(value) => value.parse().map_err(|_| {
+            AppError::ValidationError(format!("Invalid max_connections: {}", value))
+        })?,
+        None => DEFAULT_MAX_CONNECTIONS,
+    };
+    let pool = PgPoolOptions::new()
+        .max_connections(max_connections)
+        .connect(&config.connection_string)
+        .await?;
+
+    if let Some(table) = config.options.get("table") {
+        let (schema_name, table_name) = table.split_once('.').unwrap_or(("public", table));
+        let table = PostgresTable::try_new(pool, schema_name, table_name).await?;
+        return Ok(SourceTables::Table(Arc::new(table)));
+    }
+
+    if let Some(schema_name) = config.options.get("schema") {
+        let tables = list_tables(&pool, Some(schema_name)).await?;
+        let mut providers: Vec<(String, Arc<dyn TableProvider>)> = Vec::new();
+        for (_, table_name) in tables {
+            let table = PostgresTable::try_new(pool.clone(), schema_name, &table_name).await?;
+            providers.push((table_name, Arc::new(table)));
+        }
+        return Ok(SourceTables::Schema(providers));
+    }
+
+    let mut schemas: BTreeMap<String, Vec<(String, Arc<dyn TableProvider>)>> = BTreeMap::new();
+    for (schema_name, table_name) in list_tables(&pool, None).await? {
+        let table = PostgresTable::try_new(pool.clone(), &schema_name, &table_name).await?;
+        schemas
+            .entry(schema_name)
+            .or_default()
+            .push((table_name, Arc::new(table)));
+    }
+    Ok(SourceTables::Catalog(schemas.into_iter().collect()))
+}
+
+async fn list_tables(pool: &PgPool, schema_name: Option<&str>) -> AppResult<Vec<(String, String)>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT table_schema::text, table_name::text
+        FROM information_schema.tables
+        WHERE table_type IN ('BASE TABLE', 'VIEW')
+          AND table_schema NOT IN ('pg_catalog', 'information_schema')
+          AND ($1::text IS NULL OR table_schema = $1)
+        ORDER BY table_schema, table_name
+        "#,
+    )
+    .bind(schema_name)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(AppError::DatabaseError)
+}
+
+async fn introspect_columns(
+    pool: &PgPool,
+    schema_name: &str,
+    table_name: &str,
+) -> AppResult<Vec<RemoteColumn>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            column_name::text,
+            data_type::text,
+            numeric_precision::int4,
+            numeric_scale::int4,
+            is_nullable::text
+        FROM information_schema.columns
+        WHERE table_schema = $1 AND table_name = $2
+        ORDER BY ordinal_position
+        "#,
+    )
+    .bind(schema_name)
+    .bind(table_name)
+    .fetch_all(pool)
+    .await?;
+
+    let dialect = PostgresDialect;
+    let mut columns = Vec::with_capacity(rows.len());
+    for row in rows {
+        let name: String = row.try_get(0)?;
+        let data_type: String = row.try_get(1)?;
+        let precision: Option<i32> = row.try_get(2)?;
+        let scale: Option<i32> = row.try_get(3)?;
+        let nullable: String = row.try_get(4)?;
+
+        let quoted = dialect.quote_identifier(&name);
+        let (arrow_type, select_expr) = map_postgres_type(&data_type, precision, scale, &quoted);
+        columns.push(RemoteColumn {
+            name,
+            data_type: arrow_type,
+            nullable: nullable == "YES",
+            select_expr,
+        });
+    }
+
+    Ok(columns)
+}
+
+/// Maps an `information_schema` data type to Arrow, casting to text in the
+/// remote query whenever the driver has no native decoding for the type.
+pub fn map_postgres_type(
+    data_type: &str,
+    precision: Option<i32>,
+    scale: Option<i32>,
+    quoted: &str,
+) -> (DataType, String) {
+    let as_text = format!("{}::text", quoted);
+    match data_type {
+        "smallint" => (DataType::Int16, quoted.to_string()),
+        "integer" => (DataType::Int32, quoted.to_string()),
+        "bigint" => (DataType::Int64, quoted.to_string()),
+        "real" => (DataType::Float32, quoted.to_string()),
+        "double precision" => (DataType::Float64, quoted.to_string()),
+        "numeric" => match (precision, scale) {
+            (Some(p), Some(s)) if (1..=38).contains(&p) => {
+                (DataType::Decimal128(p as u8, s as i8), as_text)
+            }
+            _ => (DataType::Float64, format!("{}::float8", quoted)),
+        },
+        "boolean" => (DataType::Boolean, quoted.to_string()),
+        "text" | "character varying" | "character" | "name" => (DataType::Utf8, quoted.to_string()),
+        "date" => (DataType::Date32, quoted.to_string()),
+        "time without time zone" => (DataType::Time64(TimeUnit::Microsecond), quoted.to_string()),
+        "timestamp without time zone" => {
+            (DataType::Timestamp(TimeUnit::Microsecond, None), quoted.to_string())
+        }
+        "timestamp with time zone" => (
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            quoted.to_string(),
+        ),
+        "bytea" => (DataType::Binary, quoted.to_string()),
+        // uuid, json, jsonb, arrays, intervals, network and user-defined types
+        _ => (DataType::Utf8, as_text),
+    }
+}
+
+fn collect<T>(rows: &[PgRow], idx: usize) -> Result<Vec<Option<T>>, sqlx::Error>
+where
+    T: for<'r> sqlx::Decode<'r, Postgres> + sqlx::Type<Postgres>,
+{
+    rows.iter().map(|row| row.try_get::<Option<T>, _>(idx)).collect()
+}
+
+fn column_to_array(rows: &[PgRow], idx: usize, data_type: &DataType) -> Result<ArrayRef, sqlx::Error> {
+    let array: ArrayRef = match data_type {
+        DataType::Boolean => Arc::new(BooleanArray::from(collect::<bool>(rows, idx)?)),
+        DataType::Int16 => Arc::new(Int16Array::from(collect::<i16>(rows, idx)?)),
+        DataType::Int32 => Arc::new(Int32Array::from(collect::<i32>(rows, idx)?)),
+        DataType::Int64 => Arc::new(Int64Array::from(collect::<i64>(rows, idx)?)),
+        DataType::Float32 => Arc::new(Float32Array::from(collect::<f32>(rows, idx)?)),
+        DataType::Float64 => Arc::new(Float64Array::from(collect::<f64>(rows, idx)?)),
+        DataType::Utf8 => Arc::new(StringArray::from(collect::<String>(rows, idx)?)),
+        DataType::Binary => Arc::new(BinaryArray::from_iter(collect::<Vec<u8>>(rows, idx)?)),
+        DataType::Date32 => Arc::new(Date32Array::from(
+            collect::<chrono::NaiveDate>(rows, idx)?
+                .into_iter()
+                .map(|d| d.map(|d| d.num_days_from_ce() - EPOCH_DAYS_FROM_CE))
+                .collect::<Vec<_>>(),
+        )),
+        DataType::Time64(TimeUnit::Microsecond) => Arc::new(Time64MicrosecondArray::from(
+            collect::<chrono::NaiveTime>(rows, idx)?
+                .into_iter()
+                .map(|t| {
+                    t.map(|t| t.num_seconds_from_midnight() as i64 * 1_000_000 + (t.nanosecond() / 1_000) as i64)
+                })
+                .collect::<Vec<_>>(),
+        )),
+        DataType::Timestamp(TimeUnit::Microsecond, None) => Arc::new(TimestampMicrosecondArray::from(
+            collect::<chrono::NaiveDateTime>(rows, idx)?
+                .into_iter()
+                .map(|t| t.map(|t| t.and_utc().timestamp_micros()))
+                .collect::<Vec<_>>(),
+        )),
+        DataType::Timestamp(TimeUnit::Microsecond, Some(tz)) => Arc::new(
+            TimestampMicrosecondArray::from(
+                collect::<chrono::DateTime<chrono::Utc>>(rows, idx)?
+                    .into_iter()
+                    .map(|t| t.map(|t| t.timestamp_micros()))
+                    .collect::<Vec<_>>(),
+            )
+            .with_timezone(tz.clone()),
+        ),
+        DataType::Decimal128(precision, scale) => Arc::new(
+            Decimal128Array::from(
+                collect::<String>(rows, idx)?
+                    .into_iter()
+                    .map(|v| v.and_then(|v| parse_decimal(&v, *scale)))
+                    .collect::<Vec<_>>(),
+            )
+            .with_precision_and_scale(*precision, *scale)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        ),
+        other => {
+            return Err(sqlx::Error::Decode(
+                format!("Unsupported Arrow type for PostgreSQL column: {}", other).into(),
+            ))
+        }
+    };
+    Ok(array)
+}
+
+fn rows_to_batch(rows: &[PgRow], schema: &SchemaRef) -> DataFusionResult<RecordBatch> {
+    let arrays = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| column_to_array(rows, idx, field.data_type()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    Ok(RecordBatch::try_new_with_options(schema.clone(), arrays, &options)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_postgres_type() {
+        let (data_type, select) = map_postgres_type("numeric", Some(12), Some(2), "\"amount\"");
+        assert_eq!(data_type, DataType::Decimal128(12, 2));
+        assert_eq!(select, "\"amount\"::text");
+
+        let (data_type, select) = map_postgres_type("numeric", None, None, "\"ratio\"");
+        assert_eq!(data_type, DataType::Float64);
+        assert_eq!(select, "\"ratio\"::float8");
+
+        let (data_type, select) = map_postgres_type("jsonb", None, None, "\"payload\"");
+        assert_eq!(data_type, DataType::Utf8);
+        assert_eq!(select, "\"payload\"::text");
+
+        let (data_type, _) = map_postgres_type("timestamp with time zone", None, None, "\"ts\"");
+        assert_eq!(data_type, DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())));
+    }
+}
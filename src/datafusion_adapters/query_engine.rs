@@ -1,10 +1,12 @@
+use super::catalog::SharedCatalog;
 use crate::utils::{AppError, AppResult};
-use datafusion::execution::context::SessionContext;
+use arrow::datatypes::SchemaRef;
+use datafusion::dataframe::DataFrameWriteOptions;
+use datafusion::execution::context::SQLOptions;
 use datafusion::prelude::*;
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryRequest {
@@ -21,24 +23,29 @@ pub struct QueryResult {
     pub row_count: usize,
 }
 
+/// Options for running user SQL: only queries, no DDL, DML or other statements.
+pub fn read_only_sql_options() -> SQLOptions {
+    SQLOptions::new()
+        .with_allow_ddl(false)
+        .with_allow_dml(false)
+        .with_allow_statements(false)
+}
+
 pub struct QueryEngine {
-    ctx: Arc<RwLock<SessionContext>>,
+    catalog: Arc<SharedCatalog>,
 }
 
 impl QueryEngine {
-    pub fn new() -> Self {
-        let ctx = SessionContext::new();
-        QueryEngine {
-            ctx: Arc::new(RwLock::new(ctx)),
-        }
+    pub fn new(catalog: Arc<SharedCatalog>) -> Self {
+        QueryEngine { catalog }
     }
 
     pub async fn execute_query(&self, request: QueryRequest) -> AppResult<QueryResult> {
         let start_time = std::time::Instant::now();
-        let ctx = self.ctx.read().await;
+        let ctx = self.catalog.session_context();
         
         // Execute the SQL query
-        let df = ctx.sql(&request.sql).await
+        let df = ctx.sql_with_options(&request.sql, read_only_sql_options()).await
             .map_err(|e| AppError::DataFusionError(e))?;
         
         // Get the physical plan
@@ -81,6 +88,22 @@ impl QueryEngine {
         })
     }
 
+    /// Runs a query and writes its result as Parquet files into the directory
+    /// `path`, returning the result schema and the number of rows written.
+    pub async fn write_parquet(&self, sql: &str, path: &str) -> AppResult<(SchemaRef, u64)> {
+        let ctx = self.catalog.session_context();
+        let df = ctx.sql_with_options(sql, read_only_sql_options()).await?;
+        let schema: SchemaRef = Arc::new(df.schema().as_arrow().clone());
+
+        let counts = df.write_parquet(path, DataFrameWriteOptions::new(), None).await?;
+        let rows = counts
+            .iter()
+            .filter_map(|batch| batch.column(0).as_any().downcast_ref::<arrow::array::UInt64Array>())
+            .flat_map(|counts| counts.iter().flatten())
+            .sum();
+        Ok((schema, rows))
+    }
+
     fn batch_to_json(&self, batch: &arrow::array::RecordBatch) -> AppResult<Vec<serde_json::Value>> {
         let mut rows = Vec::new();
         
@@ -264,14 +287,14 @@ impl QueryEngine {
     }
 
     pub async fn register_table_from_csv(&self, table_name: &str, path: &str) -> AppResult<()> {
-        let ctx = self.ctx.read().await;
+        let ctx = self.catalog.session_context();
         ctx.register_csv(table_name, path, CsvReadOptions::new()).await
             .map_err(|e| AppError::DataFusionError(e))?;
         Ok(())
     }
 
     pub async fn register_table_from_parquet(&self, table_name: &str, path: &str) -> AppResult<()> {
-        let ctx = self.ctx.read().await;
+        let ctx = self.catalog.session_context();
         ctx.register_parquet(table_name, path, ParquetReadOptions::default()).await
             .map_err(|e| AppError::DataFusionError(e))?;
         Ok(())
@@ -284,7 +307,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_query_engine() {
-        let engine = QueryEngine::new();
+        let engine = QueryEngine::new(Arc::new(SharedCatalog::new()));
         
         // Register a test table
         engine.register_table_from_csv("test_table", "./test_data.csv").await.unwrap();
@@ -298,4 +321,24 @@ mod tests {
         // This test would require actual test data to run properly
         assert!(true);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_execute_query_rejects_statements() {
+        let engine = QueryEngine::new(Arc::new(SharedCatalog::new()));
+        for sql in ["CREATE TABLE t (id BIGINT)", "DROP VIEW v", "SET datafusion.execution.batch_size = 1"] {
+            let request = QueryRequest {
+                sql: sql.to_string(),
+                data_source_ids: vec![],
+                limit: None,
+            };
+            assert!(engine.execute_query(request).await.is_err(), "{}", sql);
+        }
+
+        let request = QueryRequest {
+            sql: "SELECT 1 AS one".to_string(),
+            data_source_ids: vec![],
+            limit: None,
+        };
+        assert_eq!(engine.execute_query(request).await.unwrap().row_count, 1);
+    }
+}
@@ -0,0 +1,362 @@
+use super::catalog::SourceTables;
+use super::data_source::DataSourceConfig;
+use super::sql_pushdown::{build_select, filters_pushdown, AnsiDialect, RemoteColumn, SqlDialect};
+use crate::utils::{AppError, AppResult};
+use arrow::array::{Array, BinaryArray, StringArray};
+use arrow::datatypes::SchemaRef;
+use arrow::ipc::convert::try_schema_from_ipc_buffer;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use arrow_flight::sql::CommandGetTables;
+use async_trait::async_trait;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::SessionState;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::{PartitionStream, StreamingTableExec};
+use datafusion::physical_plan::ExecutionPlan;
+use futures::{StreamExt, TryStreamExt};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tonic::transport::{Channel, Endpoint};
+
+/// A table served by another instance of this platform over Flight SQL.
+///
+/// Remote instances run DataFusion too, so every filter the pushdown layer can
+/// translate is evaluated remotely with identical semantics.
+pub struct RemoteTable {
+    channel: Channel,
+    table: String,
+    columns: Vec<RemoteColumn>,
+    schema: SchemaRef,
+}
+
+impl RemoteTable {
+    pub fn new(channel: Channel, path: &[String], schema: SchemaRef) -> Self {
+        let dialect = AnsiDialect;
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| RemoteColumn {
+                name: f.name().clone(),
+                data_type: f.data_type().clone(),
+                nullable: f.is_nullable(),
+                select_expr: dialect.quote_identifier(f.name()),
+            })
+            .collect();
+        let table = path
+            .iter()
+            .map(|part| dialect.quote_identifier(part))
+            .collect::<Vec<_>>()
+            .join(".");
+
+        RemoteTable {
+            channel,
+            table,
+            columns,
+            schema,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for RemoteTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters_pushdown(&AnsiDialect, &self.schema, filters))
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let columns: Vec<&RemoteColumn> = match projection {
+            Some(indices) => indices.iter().map(|i| &self.columns[*i]).collect(),
+            None => self.columns.iter().collect(),
+        };
+        let projected_schema = match projection {
+            Some(indices) => Arc::new(self.schema.project(indices)?),
+            None => self.schema.clone(),
+        };
+
+        let sql = build_select(&AnsiDialect, &self.table, &columns, filters, limit);
+        tracing::debug!("Remote pushdown query: {}", sql);
+
+        let partition = RemotePartition {
+            channel: self.channel.clone(),
+            sql,
+            schema: projected_schema.clone(),
+        };
+        Ok(Arc::new(StreamingTableExec::try_new(
+            projected_schema,
+            vec![Arc::new(partition)],
+            None,
+            vec![],
+            false,
+            None,
+        )?))
+    }
+}
+
+struct RemotePartition {
+    channel: Channel,
+    sql: String,
+    schema: SchemaRef,
+}
+
+impl PartitionStream for RemotePartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let channel = self.channel.clone();
+        let sql = self.sql.clone();
+        let schema = self.schema.clone();
+        let flight_error = |e: arrow_flight::error::FlightError| DataFusionError::External(Box::new(e));
+
+        let stream = futures::stream::once(async move {
+            // Sent as a Flight SQL `CommandStatementQuery`
+            let mut client = FlightSqlServiceClient::new(channel);
+            let info = client.execute(sql, None).await?;
+
+            let mut streams = Vec::new();
+            for endpoint in info.endpoint {
+                let ticket = endpoint
+                    .ticket
+                    .ok_or_else(|| DataFusionError::Execution("Remote endpoint has no ticket".to_string()))?;
+                streams.push(client.do_get(ticket).await?);
+            }
+
+            let batches = futures::stream::iter(streams)
+                .flatten()
+                .map_err(flight_error)
+                .and_then(move |batch| {
+                    let schema = schema.clone();
+                    async move { conform_batch(&schema, batch) }
+                });
+            Ok::<_, DataFusionError>(batches)
+        })
+        .try_flatten();
+
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), stream))
+    }
+}
+
+/// Re-labels a remote batch with the local schema. A scan without columns
+/// selects a constant remotely, so only its row count is kept.
+fn conform_batch(schema: &SchemaRef, batch: RecordBatch) -> DataFusionResult<RecordBatch> {
+    if schema.fields().is_empty() {
+        let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+        return Ok(RecordBatch::try_new_with_options(schema.clone(), vec![], &options)?);
+    }
+    Ok(RecordBatch::try_new(schema.clone(), batch.columns().to_vec())?)
+}
+
+/// Tables listed by the remote Flight SQL `CommandGetTables`, keyed by
+/// `[catalog, schema, table]`. A table whose schema cannot be read is logged
+/// and left out rather than failing the whole source.
+async fn discover_tables(channel: Channel) -> AppResult<Vec<(Vec<String>, SchemaRef)>> {
+    let list_error = |e: &dyn std::fmt::Display| AppError::InternalError(format!("Failed to list remote tables: {}", e));
+    let mut client = FlightSqlServiceClient::new(channel);
+    let request = CommandGetTables {
+        catalog: None,
+        db_schema_filter_pattern: None,
+        table_name_filter_pattern: None,
+        table_types: vec![],
+        include_schema: true,
+    };
+    let info = client.get_tables(request).await.map_err(|e| list_error(&e))?;
+
+    let mut tables = Vec::new();
+    for endpoint in info.endpoint {
+        let ticket = endpoint.ticket.ok_or_else(|| list_error(&"endpoint has no ticket"))?;
+        let batches: Vec<RecordBatch> = client
+            .do_get(ticket)
+            .await
+            .map_err(|e| list_error(&e))?
+            .try_collect()
+            .await
+            .map_err(|e| list_error(&e))?;
+
+        for batch in batches {
+            let strings = |name: &str| {
+                batch
+                    .column_by_name(name)
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                    .ok_or_else(|| list_error(&format!("missing column {}", name)))
+            };
+            let (catalogs, schemas, names) = (strings("catalog_name")?, strings("db_schema_name")?, strings("table_name")?);
+            let table_schemas = batch
+                .column_by_name("table_schema")
+                .and_then(|c| c.as_any().downcast_ref::<BinaryArray>())
+                .ok_or_else(|| list_error(&"missing column table_schema"))?;
+
+            for row in 0..batch.num_rows() {
+                let path = vec![
+                    catalogs.value(row).to_string(),
+                    schemas.value(row).to_string(),
+                    names.value(row).to_string(),
+                ];
+                match try_schema_from_ipc_buffer(table_schemas.value(row)) {
+                    Ok(schema) => tables.push((path, Arc::new(schema))),
+                    Err(e) => tracing::warn!("Skipping remote table {}: invalid schema: {}", path.join("."), e),
+                }
+            }
+        }
+    }
+    Ok(tables)
+}
+
+async fn connect(config: &DataSourceConfig) -> AppResult<Channel> {
+    Endpoint::from_shared(config.connection_string.clone())
+        .map_err(|e| AppError::ValidationError(format!("Invalid Flight endpoint: {}", e)))?
+        .connect()
+        .await
+        .map_err(|e| AppError::InternalError(format!("Failed to connect to remote instance: {}", e)))
+}
+
+/// Checks that the remote Flight endpoint accepts a connection.
+pub async fn ping(config: &DataSourceConfig) -> AppResult<()> {
+    connect(config).await.map(|_| ())
+}
+
+/// Connects to another instance's Flight endpoint.
+///
+/// `connection_string` is the endpoint URL, e.g. `http://warehouse-2:50051`.
+/// `options["table"]` exposes one remote table (`table`, `schema.table` or
+/// `catalog.schema.table`) and `options["schema"]` one remote schema;
+/// otherwise every schema of `options["catalog"]` (default `datafusion`) is
+/// registered in a catalog named after the data source.
+pub async fn load_tables(config: &DataSourceConfig) -> AppResult<SourceTables> {
+    let channel = connect(config).await?;
+
+    let remote_catalog = config
+        .options
+        .get("catalog")
+        .cloned()
+        .unwrap_or_else(|| super::catalog::DEFAULT_CATALOG.to_string());
+    let tables = discover_tables(channel.clone()).await?;
+
+    if let Some(name) = config.options.get("table") {
+        let parts: Vec<&str> = name.split('.').collect();
+        let (catalog, schema, table) = match parts.as_slice() {
+            [table] => (remote_catalog.as_str(), super::catalog::DEFAULT_SCHEMA, *table),
+            [schema, table] => (remote_catalog.as_str(), *schema, *table),
+            [catalog, schema, table] => (*catalog, *schema, *table),
+            _ => return Err(AppError::ValidationError(format!("Invalid table name: {}", name))),
+        };
+        let (path, table_schema) = tables
+            .into_iter()
+            .find(|(path, _)| path[0] == catalog && path[1] == schema && path[2] == table)
+            .ok_or_else(|| AppError::ValidationError(format!("Remote table {} not found", name)))?;
+        return Ok(SourceTables::Table(Arc::new(RemoteTable::new(channel, &path, table_schema))));
+    }
+
+    let mut schemas: BTreeMap<String, Vec<(String, Arc<dyn TableProvider>)>> = BTreeMap::new();
+    for (path, table_schema) in tables {
+        if path[0] != remote_catalog {
+            continue;
+        }
+        let provider: Arc<dyn TableProvider> = Arc::new(RemoteTable::new(channel.clone(), &path, table_schema));
+        schemas.entry(path[1].clone()).or_default().push((path[2].clone(), provider));
+    }
+
+    match config.options.get("schema") {
+        Some(schema) => schemas
+            .remove(schema)
+            .map(SourceTables::Schema)
+            .ok_or_else(|| AppError::ValidationError(format!("Remote schema {} not found", schema))),
+        None => Ok(SourceTables::Catalog(schemas.into_iter().collect())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::catalog::SharedCatalog;
+    use super::super::data_source::DataSourceType;
+    use super::super::flight_server::FlightSqlServer;
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::datasource::MemTable;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_query_remote_instance() {
+        // First instance serves a table over Flight
+        let upstream = Arc::new(SharedCatalog::new());
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("region", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec!["eu", "us", "eu", "apac"])),
+            ],
+        )
+        .unwrap();
+        upstream
+            .register_table("orders", Arc::new(MemTable::try_new(schema, vec![vec![batch]]).unwrap()))
+            .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = FlightSqlServer::new(upstream);
+        tokio::spawn(async move { server.serve(listener).await });
+
+        // Second instance federates it
+        let config = DataSourceConfig {
+            name: "upstream".to_string(),
+            ..DataSourceConfig::for_test(DataSourceType::Remote, format!("http://{}", addr), HashMap::new())
+        };
+        let downstream = SharedCatalog::new();
+        downstream
+            .register_source("upstream", load_tables(&config).await.unwrap())
+            .unwrap();
+
+        let batches = downstream
+            .session_context()
+            .sql("SELECT sum(id) FROM upstream.public.orders WHERE region = 'eu'")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let total = batches[0].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(total.value(0), 4);
+
+        let batches = downstream
+            .session_context()
+            .sql("SELECT count(*) FROM upstream.public.orders")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let count = batches[0].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(count.value(0), 4);
+    }
+}
@@ -0,0 +1,685 @@
+use super::catalog::SourceTables;
+use super::data_source::DataSourceConfig;
+use super::memory::{columns_to_schema, ColumnDefinition};
+use crate::utils::{AppError, AppResult};
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::json::reader::infer_json_schema_from_iterator;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use async_trait::async_trait;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::SessionState;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::{PartitionStream, StreamingTableExec};
+use datafusion::physical_plan::ExecutionPlan;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, LINK};
+use serde_json::Value;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use url::Url;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_CACHE_TTL_SECONDS: u64 = 300;
+const DEFAULT_MAX_PAGES: usize = 1000;
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_REDIRECTS: usize = 10;
+
+const REST_OPTIONS: &[&str] = &[
+    "records_path",
+    "pagination",
+    "page_param",
+    "page_start",
+    "page_size_param",
+    "page_size",
+    "offset_param",
+    "limit_param",
+    "cursor_param",
+    "cursor_path",
+    "max_pages",
+    "cache_ttl_seconds",
+    "bearer_token",
+];
+
+/// How the records of an endpoint are split across requests.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pagination {
+    None,
+    /// `?page=N`, until a page comes back empty or short of `size`.
+    Page {
+        param: String,
+        start: u64,
+        size_param: Option<String>,
+        size: Option<usize>,
+    },
+    /// `?offset=N&limit=size`, until a page comes back short.
+    Offset {
+        param: String,
+        limit_param: String,
+        size: usize,
+    },
+    /// `?cursor=...` with the cursor read from the previous response.
+    Cursor { param: String, path: String },
+    /// The `rel="next"` URL of the `Link` response header.
+    Link,
+}
+
+/// Looks up a path such as `$.data.items` or `pages[0].rows`; `$` or an
+/// empty path is the document itself.
+pub fn select_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim().trim_start_matches('$').trim_start_matches('.');
+    let mut current = value;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, indices) = segment.split_once('[').unwrap_or((segment, ""));
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indices.split('[').filter(|s| !s.is_empty()) {
+            current = current.get(index.trim_end_matches(']').parse::<usize>().ok()?)?;
+        }
+    }
+    Some(current)
+}
+
+/// The `rel="next"` target of a `Link` header, resolved against `base`.
+pub fn next_link(header: &str, base: &Url) -> Option<Url> {
+    header.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let target = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+        let is_next = parts.any(|param| {
+            let param = param.trim().replace(' ', "");
+            param == "rel=\"next\"" || param == "rel=next"
+        });
+        is_next.then(|| base.join(target).ok()).flatten()
+    })
+}
+
+/// Replaces query parameter `key` of `url`.
+fn with_param(url: &Url, key: &str, value: &str) -> Url {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| k != key)
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut().clear().extend_pairs(pairs).append_pair(key, value);
+    url
+}
+
+/// An endpoint and how to page through it.
+#[derive(Debug, Clone)]
+pub struct RestRequest {
+    url: Url,
+    headers: HeaderMap,
+    records_path: String,
+    pagination: Pagination,
+    max_pages: usize,
+}
+
+impl RestRequest {
+    pub fn from_config(config: &DataSourceConfig) -> AppResult<Self> {
+        let options = &config.options;
+        let invalid = AppError::ValidationError;
+        let mut unknown: Vec<&str> = options
+            .keys()
+            .map(String::as_str)
+            .filter(|key| !REST_OPTIONS.contains(key) && !key.starts_with("header."))
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort_unstable();
+            return Err(invalid(format!(
+                "Unknown REST option(s): {}. Supported options: {}, header.<name>",
+                unknown.join(", "),
+                REST_OPTIONS.join(", ")
+            )));
+        }
+
+        let url = Url::parse(&config.connection_string)
+            .map_err(|e| invalid(format!("Invalid REST endpoint URL: {}", e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid("A REST endpoint must be an http:// or https:// URL".to_string()));
+        }
+
+        let mut headers = HeaderMap::new();
+        for (key, value) in options {
+            if let Some(name) = key.strip_prefix("header.") {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| invalid(format!("Invalid header name: {}", name)))?;
+                let value = HeaderValue::from_str(value)
+                    .map_err(|_| invalid(format!("Invalid value for header {}", name)))?;
+                headers.insert(name, value);
+            }
+        }
+        if let Some(token) = options.get("bearer_token") {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| invalid("Invalid bearer_token".to_string()))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        let text = |key: &str, default: &str| options.get(key).cloned().unwrap_or_else(|| default.to_string());
+        let number = |key: &str| -> AppResult<Option<usize>> {
+            options
+                .get(key)
+                .map(|v| v.parse().map_err(|_| invalid(format!("Invalid {}: {}", key, v))))
+                .transpose()
+        };
+        let pagination = match options.get("pagination").map(|p| p.to_lowercase()).as_deref() {
+            None | Some("none") => Pagination::None,
+            Some("page") => Pagination::Page {
+                param: text("page_param", "page"),
+                start: number("page_start")?.unwrap_or(1) as u64,
+                size_param: options.get("page_size_param").cloned(),
+                size: number("page_size")?,
+            },
+            Some("offset") => Pagination::Offset {
+                param: text("offset_param", "offset"),
+                limit_param: text("limit_param", "limit"),
+                size: number("page_size")?.unwrap_or(DEFAULT_PAGE_SIZE).max(1),
+            },
+            Some("cursor") => Pagination::Cursor {
+                param: text("cursor_param", "cursor"),
+                path: options
+                    .get("cursor_path")
+                    .cloned()
+                    .ok_or_else(|| invalid("Cursor pagination needs options.cursor_path".to_string()))?,
+            },
+            Some("link") => Pagination::Link,
+            Some(other) => {
+                return Err(invalid(format!(
+                    "Unknown pagination {}, expected none, page, offset, cursor or link",
+                    other
+                )))
+            }
+        };
+
+        Ok(RestRequest {
+            url,
+            headers,
+            records_path: text("records_path", "$"),
+            pagination,
+            max_pages: number("max_pages")?.unwrap_or(DEFAULT_MAX_PAGES).max(1),
+        })
+    }
+
+    /// Requests every page and returns the records found in them.
+    ///
+    /// Pages are only requested from the origin of the endpoint, since the
+    /// configured headers carry its credentials; a next page elsewhere fails.
+    pub async fn fetch(&self, client: &reqwest::Client) -> AppResult<Vec<Value>> {
+        let mut records = Vec::new();
+        let mut next = Some(self.first_page_url());
+        let mut pages = 0;
+        while let Some(url) = next.take() {
+            if url.origin() != self.url.origin() {
+                return Err(AppError::ValidationError(format!(
+                    "{} links to a page on another origin, which is not followed",
+                    self.url
+                )));
+            }
+            if pages == self.max_pages {
+                tracing::warn!("Stopped paging {} after max_pages ({})", self.url, self.max_pages);
+                break;
+            }
+            pages += 1;
+
+            let response = client
+                .get(url.clone())
+                .headers(self.headers.clone())
+                .send()
+                .await
+                .map_err(|e| AppError::InternalError(format!("Request to {} failed: {}", url, e.without_url())))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(AppError::InternalError(format!("{} returned {}", url, status)));
+            }
+            let link = response
+                .headers()
+                .get(LINK)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|header| next_link(header, &url));
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| AppError::InternalError(format!("Invalid JSON from {}: {}", url, e.without_url())))?;
+
+            let page = match select_path(&body, &self.records_path) {
+                Some(Value::Array(items)) => items.clone(),
+                Some(Value::Null) | None => vec![],
+                Some(item) => vec![item.clone()],
+            };
+            let count = page.len();
+            records.extend(page);
+
+            next = match &self.pagination {
+                Pagination::None => None,
+                Pagination::Page { param, start, size, .. } => {
+                    let short = count == 0 || size.is_some_and(|size| count < size);
+                    (!short).then(|| with_param(&url, param, &(start + pages as u64).to_string()))
+                }
+                Pagination::Offset { param, size, .. } => {
+                    (count >= *size).then(|| with_param(&url, param, &(pages * size).to_string()))
+                }
+                Pagination::Cursor { param, path } => match select_path(&body, path) {
+                    Some(Value::String(cursor)) if !cursor.is_empty() => Some(with_param(&url, param, cursor)),
+                    Some(Value::Number(cursor)) => Some(with_param(&url, param, &cursor.to_string())),
+                    _ => None,
+                },
+                Pagination::Link => link,
+            };
+        }
+        Ok(records)
+    }
+
+    /// Requests the first page only and checks that it succeeds.
+    pub async fn probe(&self, client: &reqwest::Client) -> AppResult<()> {
+        let url = self.first_page_url();
+        let response = client
+            .get(url.clone())
+            .headers(self.headers.clone())
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Request to {} failed: {}", url, e.without_url())))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::InternalError(format!("{} returned {}", url, status)));
+        }
+        Ok(())
+    }
+
+    fn first_page_url(&self) -> Url {
+        match &self.pagination {
+            Pagination::Page {
+                param,
+                start,
+                size_param,
+                size,
+            } => {
+                let url = with_param(&self.url, param, &start.to_string());
+                match (size_param, size) {
+                    (Some(size_param), Some(size)) => with_param(&url, size_param, &size.to_string()),
+                    _ => url,
+                }
+            }
+            Pagination::Offset {
+                param,
+                limit_param,
+                size,
+            } => with_param(&with_param(&self.url, param, "0"), limit_param, &size.to_string()),
+            _ => self.url.clone(),
+        }
+    }
+}
+
+/// Decodes JSON records into a batch of `schema`; fields missing from a
+/// record are null and fields not in the schema are ignored.
+fn records_to_batch(schema: &SchemaRef, records: &[Value]) -> AppResult<RecordBatch> {
+    let invalid = |e: ArrowError| AppError::ValidationError(format!("Records do not match the schema: {}", e));
+    if schema.fields().is_empty() {
+        let options = RecordBatchOptions::new().with_row_count(Some(records.len()));
+        return RecordBatch::try_new_with_options(schema.clone(), vec![], &options).map_err(invalid);
+    }
+    let mut decoder = arrow::json::ReaderBuilder::new(schema.clone())
+        .with_batch_size(records.len().max(1))
+        .build_decoder()
+        .map_err(invalid)?;
+    decoder.serialize(records).map_err(invalid)?;
+    Ok(decoder
+        .flush()
+        .map_err(invalid)?
+        .unwrap_or_else(|| RecordBatch::new_empty(schema.clone())))
+}
+
+/// Records that are not JSON objects are read as a single `value` column.
+fn as_objects(records: Vec<Value>) -> Vec<Value> {
+    records
+        .into_iter()
+        .map(|record| match record {
+            Value::Object(_) => record,
+            other => serde_json::json!({ "value": other }),
+        })
+        .collect()
+}
+
+struct Snapshot {
+    fetched_at: Instant,
+    batch: RecordBatch,
+}
+
+/// The endpoint and its cached snapshot, shared by the table and its scans.
+struct RestSource {
+    request: RestRequest,
+    client: reqwest::Client,
+    schema: SchemaRef,
+    ttl: Duration,
+    snapshot: Mutex<Option<Snapshot>>,
+}
+
+impl RestSource {
+    /// The cached records, fetched again once they are older than the TTL.
+    async fn batch(&self) -> AppResult<RecordBatch> {
+        // Holding the lock while fetching lets concurrent scans share one refresh
+        let mut snapshot = self.snapshot.lock().await;
+        if let Some(cached) = snapshot.as_ref() {
+            if cached.fetched_at.elapsed() < self.ttl {
+                return Ok(cached.batch.clone());
+            }
+        }
+        let records = as_objects(self.request.fetch(&self.client).await?);
+        let batch = records_to_batch(&self.schema, &records)?;
+        tracing::debug!("Fetched {} records from {}", batch.num_rows(), self.request.url);
+        *snapshot = Some(Snapshot {
+            fetched_at: Instant::now(),
+            batch: batch.clone(),
+        });
+        Ok(batch)
+    }
+}
+
+/// Records of a JSON HTTP API, cached for `options["cache_ttl_seconds"]`.
+pub struct RestTable {
+    source: Arc<RestSource>,
+}
+
+#[async_trait]
+impl TableProvider for RestTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.source.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let projected_schema = match projection {
+            Some(indices) => Arc::new(self.source.schema.project(indices)?),
+            None => self.source.schema.clone(),
+        };
+        let partition = RestPartition {
+            source: self.source.clone(),
+            projection: projection.cloned(),
+            schema: projected_schema.clone(),
+        };
+        Ok(Arc::new(StreamingTableExec::try_new(
+            projected_schema,
+            vec![Arc::new(partition)],
+            None,
+            vec![],
+            false,
+            limit,
+        )?))
+    }
+}
+
+struct RestPartition {
+    source: Arc<RestSource>,
+    projection: Option<Vec<usize>>,
+    schema: SchemaRef,
+}
+
+impl PartitionStream for RestPartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let source = self.source.clone();
+        let projection = self.projection.clone();
+        let stream = futures::stream::once(async move {
+            let batch = source.batch().await.map_err(|e| DataFusionError::External(Box::new(e)))?;
+            match projection {
+                Some(indices) => Ok(batch.project(&indices)?),
+                None => Ok::<_, DataFusionError>(batch),
+            }
+        });
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), stream))
+    }
+}
+
+fn http_client() -> AppResult<reqwest::Client> {
+    // Redirects stay on the endpoint's origin, like the pages themselves
+    let redirects = reqwest::redirect::Policy::custom(|attempt| {
+        let same_origin = attempt
+            .previous()
+            .first()
+            .is_some_and(|first| first.origin() == attempt.url().origin());
+        if !same_origin {
+            attempt.stop()
+        } else if attempt.previous().len() > MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    });
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(redirects)
+        .build()
+        .map_err(|e| AppError::InternalError(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// Checks that the first page of the endpoint can be requested.
+pub async fn ping(config: &DataSourceConfig) -> AppResult<()> {
+    RestRequest::from_config(config)?.probe(&http_client()?).await
+}
+
+/// Opens a JSON HTTP API as a table.
+///
+/// `connection_string` is the endpoint URL. Records are taken from
+/// `options["records_path"]` of each response and pages are followed as set
+/// by `options["pagination"]`. Columns given by the user in `config.columns`
+/// skip inference from the first snapshot, which also warms the cache.
+pub async fn load_tables(config: &DataSourceConfig) -> AppResult<SourceTables> {
+    let request = RestRequest::from_config(config)?;
+    let ttl = match config.options.get("cache_ttl_seconds") {
+        Some(value) => value
+            .parse()
+            .map_err(|_| AppError::ValidationError(format!("Invalid cache_ttl_seconds: {}", value)))?,
+        None => DEFAULT_CACHE_TTL_SECONDS,
+    };
+    let client = http_client()?;
+
+    let records = as_objects(request.fetch(&client).await?);
+    let columns = config
+        .columns
+        .as_deref()
+        .map(|columns| {
+            let columns: Vec<ColumnDefinition> = serde_json::from_str(columns)
+                .map_err(|e| AppError::ValidationError(format!("Invalid column schema: {}", e)))?;
+            columns_to_schema(&columns)
+        })
+        .transpose()?;
+    let schema = match columns {
+        Some(schema) => schema,
+        None if records.is_empty() => {
+            return Err(AppError::ValidationError(format!(
+                "{} returned no records to infer a schema from; pass columns instead",
+                request.url
+            )))
+        }
+        None => Arc::new(
+            infer_json_schema_from_iterator(records.iter().map(Ok))
+                .map_err(|e| AppError::ValidationError(format!("Cannot infer a schema: {}", e)))?,
+        ),
+    };
+    let batch = records_to_batch(&schema, &records)?;
+
+    let source = RestSource {
+        request,
+        client,
+        schema,
+        ttl: Duration::from_secs(ttl),
+        snapshot: Mutex::new(Some(Snapshot {
+            fetched_at: Instant::now(),
+            batch,
+        })),
+    };
+    Ok(SourceTables::Table(Arc::new(RestTable {
+        source: Arc::new(source),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafusion_adapters::catalog::SharedCatalog;
+    use crate::datafusion_adapters::data_source::DataSourceType;
+    use crate::datafusion_adapters::test_utils::{count, source_config};
+    use axum::extract::{Query, State};
+    use axum::http::header;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type Params = Query<HashMap<String, String>>;
+
+    async fn pages(State(hits): State<Arc<AtomicUsize>>, Query(params): Params) -> Json<Value> {
+        hits.fetch_add(1, Ordering::SeqCst);
+        let items = match params.get("page").map(String::as_str) {
+            Some("1") => serde_json::json!([{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]),
+            Some("2") => serde_json::json!([{"id": 3, "name": "c", "extra": true}]),
+            _ => serde_json::json!([]),
+        };
+        Json(serde_json::json!({ "data": { "items": items } }))
+    }
+
+    async fn cursor(Query(params): Params) -> Json<Value> {
+        Json(match params.get("cursor").map(String::as_str) {
+            None => serde_json::json!({"results": [{"id": 1}, {"id": 2}], "next": "b"}),
+            Some("b") => serde_json::json!({"results": [{"id": 3}], "next": null}),
+            _ => serde_json::json!({"results": []}),
+        })
+    }
+
+    async fn linked(Query(params): Params) -> impl IntoResponse {
+        match params.get("after").map(String::as_str) {
+            None => (
+                [(header::LINK, "</linked?after=2>; rel=\"next\", </linked>; rel=\"first\"")],
+                Json(serde_json::json!([{"id": 1}, {"id": 2}])),
+            )
+                .into_response(),
+            _ => Json(serde_json::json!([{"id": 3}])).into_response(),
+        }
+    }
+
+    async fn foreign(Query(params): Params) -> impl IntoResponse {
+        let next = params.get("to").cloned().unwrap_or_default();
+        (
+            [(header::LINK, format!("<{}>; rel=\"next\"", next))],
+            Json(serde_json::json!([{"id": 1}])),
+        )
+    }
+
+    async fn serve() -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route("/pages", get(pages))
+            .route("/cursor", get(cursor))
+            .route("/linked", get(linked))
+            .route("/foreign", get(foreign))
+            .with_state(hits.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}", address), hits)
+    }
+
+    fn rest_config(url: String, options: &[(&str, &str)]) -> DataSourceConfig {
+        source_config(DataSourceType::REST, "api", url, options)
+    }
+
+    async fn load_and_count(catalog: &SharedCatalog, config: &DataSourceConfig) -> i64 {
+        catalog.register_source("api", load_tables(config).await.unwrap()).unwrap();
+        count(&catalog.session_context(), "SELECT count(*) FROM api").await
+    }
+
+    #[test]
+    fn test_select_path_and_links() {
+        let doc = serde_json::json!({"data": {"pages": [{"rows": [1, 2]}]}});
+        assert_eq!(select_path(&doc, "$.data.pages[0].rows"), Some(&serde_json::json!([1, 2])));
+        assert_eq!(select_path(&doc, "$"), Some(&doc));
+        assert_eq!(select_path(&doc, "data.missing"), None);
+
+        let base = Url::parse("https://api.example.com/items?page=1").unwrap();
+        let next = next_link("<https://api.example.com/items?page=1>; rel=\"prev\", </items?page=3>; rel=\"next\"", &base);
+        assert_eq!(next.unwrap().as_str(), "https://api.example.com/items?page=3");
+        assert_eq!(next_link("</items?page=1>; rel=\"first\"", &base), None);
+    }
+
+    #[tokio::test]
+    async fn test_rest_pagination_and_cache() {
+        let (base, hits) = serve().await;
+        let catalog = SharedCatalog::new();
+
+        let config = rest_config(
+            format!("{}/pages", base),
+            &[("records_path", "$.data.items"), ("pagination", "page"), ("cache_ttl_seconds", "60")],
+        );
+        assert_eq!(load_and_count(&catalog, &config).await, 3);
+        // Two pages and the empty one that ends paging, fetched once at load
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        let batches = catalog
+            .session_context()
+            .sql("SELECT name FROM api WHERE id = 3")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        let uncached = rest_config(
+            format!("{}/pages", base),
+            &[("records_path", "data.items"), ("pagination", "page"), ("cache_ttl_seconds", "0")],
+        );
+        load_and_count(&catalog, &uncached).await;
+        assert_eq!(hits.load(Ordering::SeqCst), 9);
+
+        let cursor = rest_config(
+            format!("{}/cursor", base),
+            &[("records_path", "results"), ("pagination", "cursor"), ("cursor_path", "next")],
+        );
+        assert_eq!(load_and_count(&catalog, &cursor).await, 3);
+
+        let linked = rest_config(format!("{}/linked", base), &[("pagination", "link")]);
+        assert_eq!(load_and_count(&catalog, &linked).await, 3);
+
+        assert!(load_tables(&rest_config(format!("{}/pages", base), &[("pagination", "scroll")]))
+            .await
+            .is_err());
+        assert!(load_tables(&rest_config(format!("{}/missing", base), &[])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_links_to_other_origins_are_not_followed() {
+        let (base, _) = serve().await;
+        let (other, other_hits) = serve().await;
+
+        let url = with_param(
+            &Url::parse(&format!("{}/foreign", base)).unwrap(),
+            "to",
+            &format!("{}/pages?page=1", other),
+        );
+        let config = rest_config(url.to_string(), &[("pagination", "link"), ("bearer_token", "s3cret")]);
+        assert!(load_tables(&config).await.is_err());
+        assert_eq!(other_hits.load(Ordering::SeqCst), 0);
+    }
+}
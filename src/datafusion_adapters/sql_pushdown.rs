@@ -0,0 +1,599 @@
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::logical_expr::{Between, BinaryExpr, Expr, Like, Operator, TableProviderFilterPushDown};
+use datafusion::scalar::ScalarValue;
+use std::sync::Arc;
+
+/// Rows per batch when streaming the result of a remote query.
+pub const FETCH_BATCH_SIZE: usize = 8192;
+
+/// Per-database quirks needed to turn DataFusion expressions into remote SQL.
+pub trait SqlDialect: Send + Sync {
+    fn quote_identifier(&self, ident: &str) -> String;
+
+    fn boolean_literal(&self, value: bool) -> String {
+        if value { "TRUE" } else { "FALSE" }.to_string()
+    }
+
+    fn supports_ilike(&self) -> bool {
+        false
+    }
+
+    /// Whether `=`/`<` on strings behaves like DataFusion (byte-wise, case sensitive).
+    /// When it does not, string predicates are still pushed down but re-checked locally.
+    fn case_sensitive_strings(&self) -> bool {
+        true
+    }
+
+    fn case_sensitive_like(&self) -> bool {
+        true
+    }
+
+    /// Whether `<`/`>` on strings order them byte-wise like DataFusion, rather
+    /// than by a collation.
+    fn binary_string_order(&self) -> bool {
+        true
+    }
+
+    /// SQL reading `column` in a filter.
+    fn column_expr(&self, column: &str) -> String {
+        self.quote_identifier(column)
+    }
+}
+
+/// A dialect whose filters read each column through its `select_expr`, so a
+/// pushed filter compares the same values DataFusion gets after a cast.
+///
+/// Decimal columns are the exception: they are only selected as text so the
+/// driver can decode them, and a decimal literal must be compared against the
+/// raw numeric column instead.
+pub struct SelectedColumns<'a> {
+    pub dialect: &'a dyn SqlDialect,
+    pub columns: &'a [RemoteColumn],
+}
+
+impl SqlDialect for SelectedColumns<'_> {
+    fn quote_identifier(&self, ident: &str) -> String {
+        self.dialect.quote_identifier(ident)
+    }
+
+    fn boolean_literal(&self, value: bool) -> String {
+        self.dialect.boolean_literal(value)
+    }
+
+    fn supports_ilike(&self) -> bool {
+        self.dialect.supports_ilike()
+    }
+
+    fn case_sensitive_strings(&self) -> bool {
+        self.dialect.case_sensitive_strings()
+    }
+
+    fn case_sensitive_like(&self) -> bool {
+        self.dialect.case_sensitive_like()
+    }
+
+    fn binary_string_order(&self) -> bool {
+        self.dialect.binary_string_order()
+    }
+
+    fn column_expr(&self, column: &str) -> String {
+        match self.columns.iter().find(|c| c.name == column) {
+            Some(c) if !matches!(c.data_type, DataType::Decimal128(_, _)) => c.select_expr.clone(),
+            _ => self.dialect.column_expr(column),
+        }
+    }
+}
+
+pub struct PostgresDialect;
+
+impl SqlDialect for PostgresDialect {
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("\"{}\"", ident.replace('"', "\"\""))
+    }
+
+    fn supports_ilike(&self) -> bool {
+        true
+    }
+
+    // Text is ordered by the database collation, e.g. `en_US.UTF-8`; equality
+    // stays byte-wise with deterministic collations.
+    fn binary_string_order(&self) -> bool {
+        false
+    }
+}
+
+pub struct MySqlDialect;
+
+impl SqlDialect for MySqlDialect {
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("`{}`", ident.replace('`', "``"))
+    }
+
+    // The default collations compare strings case-insensitively and ignore
+    // trailing spaces.
+    fn case_sensitive_strings(&self) -> bool {
+        false
+    }
+}
+
+pub struct SqliteDialect;
+
+impl SqlDialect for SqliteDialect {
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("\"{}\"", ident.replace('"', "\"\""))
+    }
+
+    fn boolean_literal(&self, value: bool) -> String {
+        if value { "1" } else { "0" }.to_string()
+    }
+
+    // LIKE ignores ASCII case unless `PRAGMA case_sensitive_like` is set.
+    fn case_sensitive_like(&self) -> bool {
+        false
+    }
+}
+
+/// Standard SQL quoting, used when the remote database is not known in advance.
+pub struct AnsiDialect;
+
+impl SqlDialect for AnsiDialect {
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("\"{}\"", ident.replace('"', "\"\""))
+    }
+}
+
+/// A column of a remote table together with the expression used to select it.
+///
+/// `select_expr` usually is just the quoted column name, but can contain a cast
+/// when the remote type has no direct driver mapping (e.g. `"amount"::text`).
+#[derive(Debug, Clone)]
+pub struct RemoteColumn {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+    pub select_expr: String,
+}
+
+pub fn columns_to_schema(columns: &[RemoteColumn]) -> SchemaRef {
+    Arc::new(Schema::new(
+        columns
+            .iter()
+            .map(|c| Field::new(&c.name, c.data_type.clone(), c.nullable))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Reports how far each filter can be pushed into the remote query.
+pub fn filters_pushdown(
+    dialect: &dyn SqlDialect,
+    schema: &Schema,
+    filters: &[&Expr],
+) -> Vec<TableProviderFilterPushDown> {
+    filters
+        .iter()
+        .map(|filter| match filter_to_sql(dialect, filter) {
+            None => TableProviderFilterPushDown::Unsupported,
+            Some(_) if !dialect.case_sensitive_strings() && references_string(schema, filter) => {
+                TableProviderFilterPushDown::Inexact
+            }
+            Some(_) if !dialect.case_sensitive_like() && contains_like(filter) => {
+                TableProviderFilterPushDown::Inexact
+            }
+            Some(_) if !dialect.binary_string_order() && orders_strings(schema, filter) => {
+                TableProviderFilterPushDown::Inexact
+            }
+            Some(_) => TableProviderFilterPushDown::Exact,
+        })
+        .collect()
+}
+
+fn references_string(schema: &Schema, expr: &Expr) -> bool {
+    expr.to_columns()
+        .map(|columns| {
+            columns.iter().any(|c| {
+                schema
+                    .field_with_name(&c.name)
+                    .map(|f| {
+                        matches!(
+                            f.data_type(),
+                            DataType::Utf8 | DataType::LargeUtf8 | DataType::Dictionary(_, _)
+                        )
+                    })
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Whether `expr` compares a string column with `<`, `<=`, `>`, `>=` or `BETWEEN`.
+fn orders_strings(schema: &Schema, expr: &Expr) -> bool {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
+            Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq => references_string(schema, expr),
+            _ => orders_strings(schema, left) || orders_strings(schema, right),
+        },
+        Expr::Between(between) => references_string(schema, &between.expr),
+        Expr::Not(inner) => orders_strings(schema, inner),
+        _ => false,
+    }
+}
+
+fn contains_like(expr: &Expr) -> bool {
+    match expr {
+        Expr::Like(_) => true,
+        Expr::Not(inner) => contains_like(inner),
+        Expr::BinaryExpr(BinaryExpr { left, right, .. }) => contains_like(left) || contains_like(right),
+        _ => false,
+    }
+}
+
+/// Builds the remote `SELECT` for a scan.
+///
+/// `table` must already be quoted. Filters that cannot be translated are
+/// skipped, and the limit is only applied when every filter made it into SQL.
+pub fn build_select(
+    dialect: &dyn SqlDialect,
+    table: &str,
+    columns: &[&RemoteColumn],
+    filters: &[Expr],
+    limit: Option<usize>,
+) -> String {
+    let select_list = if columns.is_empty() {
+        "1".to_string()
+    } else {
+        columns
+            .iter()
+            .map(|c| format!("{} AS {}", c.select_expr, dialect.quote_identifier(&c.name)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut sql = format!("SELECT {} FROM {}", select_list, table);
+
+    let predicates: Vec<String> = filters
+        .iter()
+        .filter_map(|f| filter_to_sql(dialect, f))
+        .collect();
+    if !predicates.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&predicates.join(" AND "));
+    }
+
+    if let Some(limit) = limit {
+        if predicates.len() == filters.len() {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+    }
+
+    sql
+}
+
+/// Translates a filter into a SQL predicate, or `None` if it cannot be pushed down.
+pub fn filter_to_sql(dialect: &dyn SqlDialect, expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let op_sql = match op {
+                Operator::And => {
+                    return Some(format!(
+                        "({} AND {})",
+                        filter_to_sql(dialect, left)?,
+                        filter_to_sql(dialect, right)?
+                    ))
+                }
+                Operator::Or => {
+                    return Some(format!(
+                        "({} OR {})",
+                        filter_to_sql(dialect, left)?,
+                        filter_to_sql(dialect, right)?
+                    ))
+                }
+                Operator::Eq => "=",
+                Operator::NotEq => "<>",
+                Operator::Lt => "<",
+                Operator::LtEq => "<=",
+                Operator::Gt => ">",
+                Operator::GtEq => ">=",
+                _ => return None,
+            };
+            Some(format!(
+                "{} {} {}",
+                operand_to_sql(dialect, left)?,
+                op_sql,
+                operand_to_sql(dialect, right)?
+            ))
+        }
+        Expr::IsNull(inner) => Some(format!("{} IS NULL", column_to_sql(dialect, inner)?)),
+        Expr::IsNotNull(inner) => Some(format!("{} IS NOT NULL", column_to_sql(dialect, inner)?)),
+        Expr::Not(inner) => Some(format!("NOT ({})", filter_to_sql(dialect, inner)?)),
+        Expr::InList(in_list) => {
+            let column = column_to_sql(dialect, &in_list.expr)?;
+            let values = in_list
+                .list
+                .iter()
+                .map(|e| match e {
+                    Expr::Literal(value) => literal_to_sql(dialect, value),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            if values.is_empty() {
+                return None;
+            }
+            Some(format!(
+                "{} {}IN ({})",
+                column,
+                if in_list.negated { "NOT " } else { "" },
+                values.join(", ")
+            ))
+        }
+        Expr::Between(Between {
+            expr,
+            negated,
+            low,
+            high,
+        }) => Some(format!(
+            "{} {}BETWEEN {} AND {}",
+            column_to_sql(dialect, expr)?,
+            if *negated { "NOT " } else { "" },
+            operand_to_sql(dialect, low)?,
+            operand_to_sql(dialect, high)?
+        )),
+        Expr::Like(Like {
+            negated,
+            expr,
+            pattern,
+            escape_char,
+            case_insensitive,
+        }) => {
+            if *case_insensitive && !dialect.supports_ilike() {
+                return None;
+            }
+            let keyword = if *case_insensitive { "ILIKE" } else { "LIKE" };
+            let mut sql = format!(
+                "{} {}{} {}",
+                column_to_sql(dialect, expr)?,
+                if *negated { "NOT " } else { "" },
+                keyword,
+                operand_to_sql(dialect, pattern)?
+            );
+            if let Some(escape) = escape_char {
+                sql.push_str(&format!(" ESCAPE {}", quote_string(&escape.to_string())));
+            }
+            Some(sql)
+        }
+        _ => None,
+    }
+}
+
+fn operand_to_sql(dialect: &dyn SqlDialect, expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Column(_) => column_to_sql(dialect, expr),
+        Expr::Literal(value) => literal_to_sql(dialect, value),
+        _ => None,
+    }
+}
+
+fn column_to_sql(dialect: &dyn SqlDialect, expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Column(column) => Some(dialect.column_expr(&column.name)),
+        _ => None,
+    }
+}
+
+fn quote_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+pub fn literal_to_sql(dialect: &dyn SqlDialect, value: &ScalarValue) -> Option<String> {
+    if value.is_null() {
+        return Some("NULL".to_string());
+    }
+
+    match value {
+        ScalarValue::Boolean(Some(v)) => Some(dialect.boolean_literal(*v)),
+        ScalarValue::Int8(Some(v)) => Some(v.to_string()),
+        ScalarValue::Int16(Some(v)) => Some(v.to_string()),
+        ScalarValue::Int32(Some(v)) => Some(v.to_string()),
+        ScalarValue::Int64(Some(v)) => Some(v.to_string()),
+        ScalarValue::UInt8(Some(v)) => Some(v.to_string()),
+        ScalarValue::UInt16(Some(v)) => Some(v.to_string()),
+        ScalarValue::UInt32(Some(v)) => Some(v.to_string()),
+        ScalarValue::UInt64(Some(v)) => Some(v.to_string()),
+        ScalarValue::Float32(Some(v)) if v.is_finite() => Some(v.to_string()),
+        ScalarValue::Float64(Some(v)) if v.is_finite() => Some(v.to_string()),
+        ScalarValue::Decimal128(Some(v), _, scale) => Some(format_decimal(*v, *scale)),
+        ScalarValue::Utf8(Some(v)) | ScalarValue::LargeUtf8(Some(v)) => Some(quote_string(v)),
+        ScalarValue::Dictionary(_, inner) => literal_to_sql(dialect, inner),
+        ScalarValue::Date32(Some(days)) => {
+            let date = chrono::NaiveDate::from_num_days_from_ce_opt(*days + EPOCH_DAYS_FROM_CE)?;
+            Some(quote_string(&date.to_string()))
+        }
+        ScalarValue::TimestampSecond(Some(v), None) => {
+            timestamp_literal(chrono::DateTime::from_timestamp(*v, 0)?)
+        }
+        ScalarValue::TimestampMillisecond(Some(v), None) => {
+            timestamp_literal(chrono::DateTime::from_timestamp_millis(*v)?)
+        }
+        ScalarValue::TimestampMicrosecond(Some(v), None) => {
+            timestamp_literal(chrono::DateTime::from_timestamp_micros(*v)?)
+        }
+        _ => None,
+    }
+}
+
+fn timestamp_literal(value: chrono::DateTime<chrono::Utc>) -> Option<String> {
+    Some(quote_string(
+        &value.naive_utc().format("%Y-%m-%d %H:%M:%S%.6f").to_string(),
+    ))
+}
+
+/// Days between 0001-01-01 and 1970-01-01, for converting Arrow `Date32` values.
+pub const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+pub fn format_decimal(value: i128, scale: i8) -> String {
+    if scale <= 0 {
+        return (value * 10i128.pow((-scale) as u32)).to_string();
+    }
+    let divisor = 10i128.pow(scale as u32);
+    let sign = if value < 0 { "-" } else { "" };
+    let abs = value.abs();
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        abs / divisor,
+        abs % divisor,
+        width = scale as usize
+    )
+}
+
+/// Parses a decimal rendered as text into the unscaled `i128` used by `Decimal128`.
+/// Extra fractional digits beyond `scale` are truncated.
+pub fn parse_decimal(text: &str, scale: i8) -> Option<i128> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+
+    let scale = scale.max(0) as usize;
+    let mut frac: String = frac_part.chars().take(scale).collect();
+    while frac.len() < scale {
+        frac.push('0');
+    }
+
+    let unscaled: i128 = format!("{}{}", if int_part.is_empty() { "0" } else { int_part }, frac)
+        .parse()
+        .ok()?;
+    Some(if negative { -unscaled } else { unscaled })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::prelude::{col, lit};
+
+    #[test]
+    fn test_filter_to_sql() {
+        let dialect = PostgresDialect;
+        let filter = col("age").gt(lit(30)).and(col("name").eq(lit("O'Brien")));
+        assert_eq!(
+            filter_to_sql(&dialect, &filter).unwrap(),
+            "(\"age\" > 30 AND \"name\" = 'O''Brien')"
+        );
+
+        let unsupported = (col("age") + lit(1)).gt(lit(30));
+        assert!(filter_to_sql(&dialect, &unsupported).is_none());
+    }
+
+    #[test]
+    fn test_build_select_skips_limit_with_untranslated_filter() {
+        let dialect = PostgresDialect;
+        let column = RemoteColumn {
+            name: "id".to_string(),
+            data_type: DataType::Int64,
+            nullable: false,
+            select_expr: "\"id\"".to_string(),
+        };
+
+        let pushed = build_select(&dialect, "\"t\"", &[&column], &[col("id").eq(lit(1))], Some(5));
+        assert_eq!(pushed, "SELECT \"id\" AS \"id\" FROM \"t\" WHERE \"id\" = 1 LIMIT 5");
+
+        let partial = build_select(
+            &dialect,
+            "\"t\"",
+            &[&column],
+            &[(col("id") + lit(1)).eq(lit(2))],
+            Some(5),
+        );
+        assert_eq!(partial, "SELECT \"id\" AS \"id\" FROM \"t\"");
+    }
+
+    #[test]
+    fn test_string_filters_inexact_for_case_insensitive_dialect() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let by_id = col("id").eq(lit(1));
+        let by_name = col("name").eq(lit("a"));
+
+        let pushdown = filters_pushdown(&MySqlDialect, &schema, &[&by_id, &by_name]);
+        assert_eq!(pushdown[0], TableProviderFilterPushDown::Exact);
+        assert_eq!(pushdown[1], TableProviderFilterPushDown::Inexact);
+    }
+
+    #[test]
+    fn test_string_ranges_inexact_with_collation() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let by_id = col("id").lt(lit(10));
+        let by_name = col("name").eq(lit("a"));
+        let name_range = col("id").eq(lit(1)).and(col("name").gt(lit("a")));
+        let name_between = col("name").between(lit("a"), lit("m"));
+
+        let pushdown = filters_pushdown(&PostgresDialect, &schema, &[&by_id, &by_name, &name_range, &name_between]);
+        assert_eq!(pushdown[0], TableProviderFilterPushDown::Exact);
+        assert_eq!(pushdown[1], TableProviderFilterPushDown::Exact);
+        assert_eq!(pushdown[2], TableProviderFilterPushDown::Inexact);
+        assert_eq!(pushdown[3], TableProviderFilterPushDown::Inexact);
+    }
+
+    #[test]
+    fn test_filters_read_cast_columns() {
+        let columns = [RemoteColumn {
+            name: "score".to_string(),
+            data_type: DataType::Float64,
+            nullable: true,
+            select_expr: "CAST(\"score\" AS REAL)".to_string(),
+        }];
+        let dialect = SelectedColumns {
+            dialect: &SqliteDialect,
+            columns: &columns,
+        };
+        let filter = col("score").gt(lit(7.5)).and(col("other").is_null());
+        assert_eq!(
+            filter_to_sql(&dialect, &filter).unwrap(),
+            "(CAST(\"score\" AS REAL) > 7.5 AND \"other\" IS NULL)"
+        );
+        let select = build_select(&dialect, "\"t\"", &[&columns[0]], &[col("score").lt(lit(1.0))], Some(5));
+        assert_eq!(
+            select,
+            "SELECT CAST(\"score\" AS REAL) AS \"score\" FROM \"t\" WHERE CAST(\"score\" AS REAL) < 1 LIMIT 5"
+        );
+    }
+
+    #[test]
+    fn test_decimal_filters_read_raw_columns() {
+        let columns = [RemoteColumn {
+            name: "amount".to_string(),
+            data_type: DataType::Decimal128(10, 2),
+            nullable: true,
+            select_expr: "\"amount\"::text".to_string(),
+        }];
+        let dialect = SelectedColumns {
+            dialect: &PostgresDialect,
+            columns: &columns,
+        };
+        let filter = col("amount").gt(lit(ScalarValue::Decimal128(Some(1250), 10, 2)));
+        assert_eq!(filter_to_sql(&dialect, &filter).unwrap(), "\"amount\" > 12.50");
+
+        let select = build_select(&dialect, "\"t\"", &[&columns[0]], &[filter], None);
+        assert_eq!(
+            select,
+            "SELECT \"amount\"::text AS \"amount\" FROM \"t\" WHERE \"amount\" > 12.50"
+        );
+    }
+
+    #[test]
+    fn test_decimal_round_trip() {
+        assert_eq!(parse_decimal("-12.345", 2), Some(-1234));
+        assert_eq!(parse_decimal("7", 3), Some(7000));
+        assert_eq!(format_decimal(-1234, 2), "-12.34");
+        assert_eq!(format_decimal(5, 3), "0.005");
+    }
+}
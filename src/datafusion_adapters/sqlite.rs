@@ -0,0 +1,461 @@
+use super::catalog::SourceTables;
+use super::data_source::DataSourceConfig;
+use super::sql_pushdown::{
+    build_select, columns_to_schema, filters_pushdown, RemoteColumn, SelectedColumns, SqlDialect, SqliteDialect,
+    FETCH_BATCH_SIZE,
+};
+use crate::utils::{AppError, AppResult};
+use arrow::array::*;
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use async_trait::async_trait;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::SessionState;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::streaming::{PartitionStream, StreamingTableExec};
+use datafusion::physical_plan::ExecutionPlan;
+use futures::{StreamExt, TryStreamExt};
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqliteConnection, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{Connection, Row};
+use std::any::Any;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const DEFAULT_SAMPLE_ROWS: usize = 1000;
+
+/// Column type affinity as defined by SQLite's declared-type rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+/// Applies the affinity rules from <https://www.sqlite.org/datatype3.html>, in order.
+pub fn affinity(declared_type: &str) -> Affinity {
+    let declared = declared_type.to_uppercase();
+    if declared.contains("INT") {
+        Affinity::Integer
+    } else if declared.contains("CHAR") || declared.contains("CLOB") || declared.contains("TEXT") {
+        Affinity::Text
+    } else if declared.is_empty() || declared.contains("BLOB") {
+        Affinity::Blob
+    } else if declared.contains("REAL") || declared.contains("FLOA") || declared.contains("DOUB") {
+        Affinity::Real
+    } else {
+        Affinity::Numeric
+    }
+}
+
+/// Picks an Arrow type from the declared type and the storage classes
+/// (`typeof()` results) seen in a sample of the column's values.
+///
+/// Affinity only says how SQLite *prefers* to store values, so samples win
+/// whenever they contradict it, e.g. text stored in an `INTEGER` column.
+pub fn infer_type(declared_type: &str, samples: &HashMap<String, i64>) -> DataType {
+    let seen = |class: &str| samples.get(class).copied().unwrap_or(0) > 0;
+    let has_text = seen("text");
+    let has_blob = seen("blob");
+    let has_real = seen("real");
+    let has_integer = seen("integer");
+    let has_values = has_text || has_blob || has_real || has_integer;
+
+    if has_blob && !(has_text || has_real || has_integer) {
+        return DataType::Binary;
+    }
+    if has_text || has_blob {
+        return DataType::Utf8;
+    }
+
+    match affinity(declared_type) {
+        Affinity::Text => DataType::Utf8,
+        Affinity::Real => DataType::Float64,
+        Affinity::Integer if has_real => DataType::Float64,
+        Affinity::Integer => DataType::Int64,
+        Affinity::Numeric if declared_type.to_uppercase().contains("BOOL") && !has_real => DataType::Boolean,
+        Affinity::Numeric if has_real => DataType::Float64,
+        Affinity::Numeric if has_integer => DataType::Int64,
+        Affinity::Numeric => {
+            let declared = declared_type.to_uppercase();
+            if declared.contains("DEC") || declared.contains("NUM") {
+                DataType::Float64
+            } else {
+                // DATE, DATETIME and friends are stored as text by convention
+                DataType::Utf8
+            }
+        }
+        Affinity::Blob if has_values && has_real => DataType::Float64,
+        Affinity::Blob if has_values => DataType::Int64,
+        Affinity::Blob if declared_type.is_empty() => DataType::Utf8,
+        Affinity::Blob => DataType::Binary,
+    }
+}
+
+fn select_expr(quoted: &str, data_type: &DataType) -> String {
+    // Casting keeps every value decodable even when a row disagrees with the
+    // column's inferred type.
+    match data_type {
+        DataType::Int64 | DataType::Boolean => format!("CAST({} AS INTEGER)", quoted),
+        DataType::Float64 => format!("CAST({} AS REAL)", quoted),
+        DataType::Binary => format!("CAST({} AS BLOB)", quoted),
+        _ => format!("CAST({} AS TEXT)", quoted),
+    }
+}
+
+/// A single SQLite table or view exposed to DataFusion.
+pub struct SqliteTable {
+    pool: SqlitePool,
+    table: String,
+    columns: Vec<RemoteColumn>,
+    schema: SchemaRef,
+}
+
+impl SqliteTable {
+    /// Every column is selected through a cast, and filters read the cast value too.
+    fn dialect(&self) -> SelectedColumns<'_> {
+        SelectedColumns {
+            dialect: &SqliteDialect,
+            columns: &self.columns,
+        }
+    }
+
+    pub async fn try_new(pool: SqlitePool, table_name: &str, sample_rows: usize) -> AppResult<Self> {
+        let columns = introspect_columns(&pool, table_name, sample_rows).await?;
+        if columns.is_empty() {
+            return Err(AppError::ValidationError(format!(
+                "Table {} not found or has no columns",
+                table_name
+            )));
+        }
+
+        Ok(SqliteTable {
+            pool,
+            table: SqliteDialect.quote_identifier(table_name),
+            schema: columns_to_schema(&columns),
+            columns,
+        })
+    }
+}
+
+#[async_trait]
+impl TableProvider for SqliteTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters_pushdown(&self.dialect(), &self.schema, filters))
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let columns: Vec<&RemoteColumn> = match projection {
+            Some(indices) => indices.iter().map(|i| &self.columns[*i]).collect(),
+            None => self.columns.iter().collect(),
+        };
+        let projected_schema = match projection {
+            Some(indices) => Arc::new(self.schema.project(indices)?),
+            None => self.schema.clone(),
+        };
+
+        let sql = build_select(&self.dialect(), &self.table, &columns, filters, limit);
+        tracing::debug!("SQLite pushdown query: {}", sql);
+
+        let partition = SqlitePartition {
+            pool: self.pool.clone(),
+            sql,
+            schema: projected_schema.clone(),
+        };
+        Ok(Arc::new(StreamingTableExec::try_new(
+            projected_schema,
+            vec![Arc::new(partition)],
+            None,
+            vec![],
+            false,
+            None,
+        )?))
+    }
+}
+
+struct SqlitePartition {
+    pool: SqlitePool,
+    sql: String,
+    schema: SchemaRef,
+}
+
+impl PartitionStream for SqlitePartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let mut builder = RecordBatchReceiverStream::builder(self.schema.clone(), 2);
+        let tx = builder.tx();
+        let pool = self.pool.clone();
+        let sql = self.sql.clone();
+        let schema = self.schema.clone();
+
+        builder.spawn(async move {
+            let mut chunks = sqlx::query(&sql).fetch(&pool).try_chunks(FETCH_BATCH_SIZE);
+            while let Some(rows) = chunks.next().await {
+                let rows = rows.map_err(|e| DataFusionError::External(Box::new(e.1)))?;
+                // The receiver is gone once the query stops early, e.g. on LIMIT
+                if tx.send(rows_to_batch(&rows, &schema)).await.is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        builder.build()
+    }
+}
+
+fn connect_options(config: &DataSourceConfig) -> AppResult<SqliteConnectOptions> {
+    let connect_options = if config.connection_string.starts_with("sqlite:") {
+        SqliteConnectOptions::from_str(&config.connection_string)?
+    } else {
+        SqliteConnectOptions::new().filename(&config.connection_string)
+    };
+    Ok(connect_options.read_only(true))
+}
+
+/// Checks that the database file opens and answers `SELECT 1`.
+pub async fn ping(config: &DataSourceConfig) -> AppResult<()> {
+    let mut connection = SqliteConnection::connect_with(&connect_options(config)?).await?;
+    sqlx::query("SELECT 1").execute(&mut connection).await?;
+    connection.close().await?;
+    Ok(())
+}
+
+/// Opens a SQLite database file read-only.
+///
+/// `connection_string` is a file path or a `sqlite://` URL. Every table and
+/// view is exposed in a schema named after the data source, unless
+/// `options["table"]` selects a single one. `options["sample_rows"]` sets how
+/// many rows are inspected per column to infer types (default 1000).
+pub async fn load_tables(config: &DataSourceConfig) -> AppResult<SourceTables> {
+    let sample_rows = match config.options.get("sample_rows") {
+        Some(value) => value
+            .parse()
+            .map_err(|_| AppError::ValidationError(format!("Invalid sample_rows: {}", value)))?,
+        None => DEFAULT_SAMPLE_ROWS,
+    };
+
+    let pool = SqlitePoolOptions::new().connect_with(connect_options(config)?).await?;
+
+    if let Some(table_name) = config.options.get("table") {
+        let table = SqliteTable::try_new(pool, table_name, sample_rows).await?;
+        return Ok(SourceTables::Table(Arc::new(table)));
+    }
+
+    let table_names: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT name FROM sqlite_master
+        WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%'
+        ORDER BY name
+        "#,
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut tables: Vec<(String, Arc<dyn TableProvider>)> = Vec::with_capacity(table_names.len());
+    for table_name in table_names {
+        let table = SqliteTable::try_new(pool.clone(), &table_name, sample_rows).await?;
+        tables.push((table_name, Arc::new(table)));
+    }
+    Ok(SourceTables::Schema(tables))
+}
+
+async fn introspect_columns(
+    pool: &SqlitePool,
+    table_name: &str,
+    sample_rows: usize,
+) -> AppResult<Vec<RemoteColumn>> {
+    let rows = sqlx::query(r#"SELECT name, type, "notnull" FROM pragma_table_info(?)"#)
+        .bind(table_name)
+        .fetch_all(pool)
+        .await?;
+
+    let dialect = SqliteDialect;
+    let quoted_table = dialect.quote_identifier(table_name);
+    let mut columns = Vec::with_capacity(rows.len());
+    for row in rows {
+        let name: String = row.try_get(0)?;
+        let declared_type: String = row.try_get(1)?;
+        let not_null: bool = row.try_get(2)?;
+        let quoted = dialect.quote_identifier(&name);
+
+        let samples: Vec<(String, i64)> = sqlx::query_as(&format!(
+            "SELECT typeof({col}), count(*) FROM (SELECT {col} FROM {table} WHERE {col} IS NOT NULL LIMIT {limit}) GROUP BY 1",
+            col = quoted,
+            table = quoted_table,
+            limit = sample_rows
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        let data_type = infer_type(&declared_type, &samples.into_iter().collect());
+        columns.push(RemoteColumn {
+            select_expr: select_expr(&quoted, &data_type),
+            name,
+            data_type,
+            nullable: !not_null,
+        });
+    }
+
+    Ok(columns)
+}
+
+fn collect<T>(rows: &[SqliteRow], idx: usize) -> Result<Vec<Option<T>>, sqlx::Error>
+where
+    T: for<'r> sqlx::Decode<'r, Sqlite> + sqlx::Type<Sqlite>,
+{
+    rows.iter().map(|row| row.try_get::<Option<T>, _>(idx)).collect()
+}
+
+fn column_to_array(rows: &[SqliteRow], idx: usize, data_type: &DataType) -> Result<ArrayRef, sqlx::Error> {
+    let array: ArrayRef = match data_type {
+        DataType::Boolean => Arc::new(BooleanArray::from(collect::<bool>(rows, idx)?)),
+        DataType::Int64 => Arc::new(Int64Array::from(collect::<i64>(rows, idx)?)),
+        DataType::Float64 => Arc::new(Float64Array::from(collect::<f64>(rows, idx)?)),
+        DataType::Utf8 => Arc::new(StringArray::from(collect::<String>(rows, idx)?)),
+        DataType::Binary => Arc::new(BinaryArray::from_iter(collect::<Vec<u8>>(rows, idx)?)),
+        other => {
+            return Err(sqlx::Error::Decode(
+                format!("Unsupported Arrow type for SQLite column: {}", other).into(),
+            ))
+        }
+    };
+    Ok(array)
+}
+
+fn rows_to_batch(rows: &[SqliteRow], schema: &SchemaRef) -> DataFusionResult<RecordBatch> {
+    let arrays = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| column_to_array(rows, idx, field.data_type()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    Ok(RecordBatch::try_new_with_options(schema.clone(), arrays, &options)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafusion_adapters::catalog::SharedCatalog;
+    use crate::datafusion_adapters::data_source::DataSourceType;
+
+    #[test]
+    fn test_affinity() {
+        assert_eq!(affinity("BIGINT"), Affinity::Integer);
+        assert_eq!(affinity("VARCHAR(255)"), Affinity::Text);
+        assert_eq!(affinity(""), Affinity::Blob);
+        assert_eq!(affinity("DOUBLE PRECISION"), Affinity::Real);
+        assert_eq!(affinity("DECIMAL(10,5)"), Affinity::Numeric);
+        // "POINT" contains "INT"
+        assert_eq!(affinity("POINT"), Affinity::Integer);
+    }
+
+    #[test]
+    fn test_infer_type_prefers_samples() {
+        let samples = |pairs: &[(&str, i64)]| -> HashMap<String, i64> {
+            pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+        };
+
+        assert_eq!(infer_type("INTEGER", &samples(&[("integer", 10)])), DataType::Int64);
+        assert_eq!(infer_type("INTEGER", &samples(&[("integer", 9), ("text", 1)])), DataType::Utf8);
+        assert_eq!(infer_type("BOOLEAN", &samples(&[("integer", 3)])), DataType::Boolean);
+        assert_eq!(infer_type("NUMERIC", &samples(&[("integer", 3), ("real", 2)])), DataType::Float64);
+        assert_eq!(infer_type("", &samples(&[("real", 3)])), DataType::Float64);
+        assert_eq!(infer_type("BLOB", &samples(&[])), DataType::Binary);
+        assert_eq!(infer_type("DATETIME", &samples(&[])), DataType::Utf8);
+    }
+
+    #[tokio::test]
+    async fn test_attach_whole_database() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("app.db");
+        let options = SqliteConnectOptions::new().filename(&path).create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, score)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE events (id INTEGER, payload BLOB)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO users VALUES (1, 'ada', 9.5), (2, 'Bob', 7), (3, 'bob', NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+
+        let config = DataSourceConfig {
+            name: "app".to_string(),
+            ..DataSourceConfig::for_test(DataSourceType::SQLite, path.to_string_lossy(), HashMap::new())
+        };
+
+        let catalog = SharedCatalog::new();
+        catalog
+            .register_source(&config.name, load_tables(&config).await.unwrap())
+            .unwrap();
+
+        let batches = catalog
+            .session_context()
+            .sql("SELECT id, score FROM app.users WHERE name LIKE 'b%'")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        // SQLite's LIKE also matches 'Bob'; DataFusion re-applies the filter
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+
+        // Filters compare the cast values the scan returns, so 7 and 9.5 are numbers
+        let batches = catalog
+            .session_context()
+            .sql("SELECT id FROM app.users WHERE score > 8 LIMIT 5")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+
+        let batches = catalog
+            .session_context()
+            .sql("SELECT count(*) FROM app.events")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+    }
+}
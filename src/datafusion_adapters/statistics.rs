@@ -0,0 +1,628 @@
+use super::materialized_view::parse_schedule;
+use crate::utils::{AppError, AppResult};
+use arrow::array::{Array, ArrayRef, Int64Array, UInt64Array};
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::common::stats::Precision;
+use datafusion::common::{
+    ColumnStatistics as DataFusionColumnStatistics, Constraints, ScalarValue, Statistics,
+};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result as DataFusionResult;
+use datafusion::execution::context::{SessionContext, SessionState, TaskContext};
+use datafusion::logical_expr::{Expr, LogicalPlan, TableProviderFilterPushDown};
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties, SendableRecordBatchStream,
+};
+use datafusion::sql::ResolvedTableReference;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Data source option holding a cron expression for scheduled analyses.
+pub const ANALYZE_SCHEDULE_OPTION: &str = "analyze_schedule";
+/// Equal-width buckets in the histogram of a numeric column.
+const HISTOGRAM_BUCKETS: usize = 10;
+
+/// The schedule of `options["analyze_schedule"]`, see [`parse_schedule`].
+pub fn analyze_schedule(options: &HashMap<String, String>) -> AppResult<Option<cron::Schedule>> {
+    options
+        .get(ANALYZE_SCHEDULE_OPTION)
+        .map(|expression| parse_schedule(expression))
+        .transpose()
+}
+
+/// Values of a numeric column between `lower` and `upper`; every bucket but
+/// the last excludes its upper bound.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnStatistics {
+    pub name: String,
+    pub data_type: String,
+    pub null_count: u64,
+    /// Smallest and largest values as text, for ordered scalar types.
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// HyperLogLog estimate of the distinct non-null values.
+    pub distinct_count: Option<u64>,
+    /// Equal-width histogram of the non-null values of numeric columns.
+    pub histogram: Option<Vec<HistogramBucket>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStatistics {
+    /// `catalog.schema.table` name of the analyzed table.
+    pub table: String,
+    pub row_count: u64,
+    pub columns: Vec<ColumnStatistics>,
+}
+
+impl TableStatistics {
+    /// Statistics for the planner, in the column order of `schema`. Columns
+    /// added or retyped since the analysis are unknown, and everything is
+    /// inexact since the data may have changed.
+    pub fn to_statistics(&self, schema: &Schema) -> Statistics {
+        let column_statistics = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let data_type = field.data_type();
+                match self
+                    .columns
+                    .iter()
+                    .find(|column| column.name == *field.name() && column.data_type == data_type.to_string())
+                {
+                    Some(column) => DataFusionColumnStatistics {
+                        null_count: Precision::Inexact(column.null_count as usize),
+                        max_value: scalar(column.max.as_deref(), data_type),
+                        min_value: scalar(column.min.as_deref(), data_type),
+                        distinct_count: column
+                            .distinct_count
+                            .map(|count| Precision::Inexact(count as usize))
+                            .unwrap_or(Precision::Absent),
+                    },
+                    None => DataFusionColumnStatistics::new_unknown(),
+                }
+            })
+            .collect();
+        Statistics {
+            num_rows: Precision::Inexact(self.row_count as usize),
+            total_byte_size: Precision::Absent,
+            column_statistics,
+        }
+    }
+}
+
+/// Statistics from the latest analysis of a data source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSourceStatistics {
+    pub data_source_id: String,
+    pub analyzed_at: chrono::NaiveDateTime,
+    pub duration_ms: u64,
+    pub tables: Vec<TableStatistics>,
+}
+
+impl DataSourceStatistics {
+    pub fn table(&self, reference: &ResolvedTableReference) -> Option<&TableStatistics> {
+        let name = reference.to_string();
+        self.tables.iter().find(|table| table.table == name)
+    }
+}
+
+fn scalar(value: Option<&str>, data_type: &DataType) -> Precision<ScalarValue> {
+    value
+        .and_then(|value| ScalarValue::try_from_string(value.to_string(), data_type).ok())
+        .map(Precision::Inexact)
+        .unwrap_or(Precision::Absent)
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn has_min_max(data_type: &DataType) -> bool {
+    data_type.is_numeric()
+        || matches!(
+            data_type,
+            DataType::Boolean
+                | DataType::Utf8
+                | DataType::LargeUtf8
+                | DataType::Date32
+                | DataType::Date64
+                | DataType::Time32(_)
+                | DataType::Time64(_)
+                | DataType::Timestamp(_, _)
+        )
+}
+
+/// `approx_distinct` over `column`, through its text for the types it does
+/// not hash itself.
+fn distinct_expr(column: &str, data_type: &DataType) -> Option<String> {
+    match data_type {
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Binary | DataType::LargeBinary => {
+            Some(format!("approx_distinct({})", column))
+        }
+        data_type if data_type.is_integer() => Some(format!("approx_distinct({})", column)),
+        data_type if has_min_max(data_type) => Some(format!("approx_distinct(CAST({} AS VARCHAR))", column)),
+        _ => None,
+    }
+}
+
+fn invalid(e: arrow::error::ArrowError) -> AppError {
+    AppError::InternalError(format!("Failed to read statistics: {}", e))
+}
+
+fn count_values(array: &ArrayRef) -> AppResult<Vec<u64>> {
+    let counts = arrow::compute::cast(array, &DataType::UInt64).map_err(invalid)?;
+    let counts = counts
+        .as_any()
+        .downcast_ref::<UInt64Array>()
+        .ok_or_else(|| AppError::InternalError("Counts are not integers".to_string()))?;
+    Ok(counts.iter().map(|count| count.unwrap_or(0)).collect())
+}
+
+fn text_value(array: &ArrayRef) -> AppResult<Option<String>> {
+    if array.is_null(0) {
+        return Ok(None);
+    }
+    arrow::util::display::array_value_to_string(array, 0)
+        .map(Some)
+        .map_err(invalid)
+}
+
+/// Computes row count, null counts, min/max, distinct estimates and
+/// histograms of the registered table `reference` in one scan, plus one
+/// per numeric column for its histogram.
+pub async fn analyze_table(
+    ctx: &SessionContext,
+    reference: &ResolvedTableReference,
+    schema: &SchemaRef,
+) -> AppResult<TableStatistics> {
+    let table = [&*reference.catalog, &*reference.schema, &*reference.table]
+        .map(quote_identifier)
+        .join(".");
+    let mut select = vec!["count(*)".to_string()];
+    for field in schema.fields() {
+        let column = quote_identifier(field.name());
+        select.push(format!("count({})", column));
+        if has_min_max(field.data_type()) {
+            select.push(format!("min({})", column));
+            select.push(format!("max({})", column));
+        }
+        if let Some(expr) = distinct_expr(&column, field.data_type()) {
+            select.push(expr);
+        }
+    }
+
+    let sql = format!("SELECT {} FROM {}", select.join(", "), table);
+    let batches = ctx.sql(&sql).await?.collect().await?;
+    let row: RecordBatch = batches
+        .into_iter()
+        .find(|batch| batch.num_rows() > 0)
+        .ok_or_else(|| AppError::InternalError(format!("Analyzing {} returned no rows", reference)))?;
+    let mut values = row.columns().iter();
+    let mut next = || {
+        values
+            .next()
+            .ok_or_else(|| AppError::InternalError(format!("Analyzing {} returned too few columns", reference)))
+    };
+
+    let row_count = count_values(next()?)?[0];
+    let mut columns = Vec::with_capacity(schema.fields().len());
+    for field in schema.fields() {
+        let column = quote_identifier(field.name());
+        let non_null = count_values(next()?)?[0];
+        let (min, max) = match has_min_max(field.data_type()) {
+            true => (text_value(next()?)?, text_value(next()?)?),
+            false => (None, None),
+        };
+        let distinct_count = match distinct_expr(&column, field.data_type()) {
+            Some(_) => Some(count_values(next()?)?[0].min(non_null)),
+            None => None,
+        };
+        let bounds = min
+            .as_deref()
+            .zip(max.as_deref())
+            .and_then(|(min, max)| Some((min.parse::<f64>().ok()?, max.parse::<f64>().ok()?)))
+            .filter(|(min, max)| min.is_finite() && max.is_finite());
+        let histogram = match bounds {
+            Some((min, max)) if field.data_type().is_numeric() && non_null > 0 => {
+                Some(histogram(ctx, &table, &column, min, max, non_null).await?)
+            }
+            _ => None,
+        };
+
+        columns.push(ColumnStatistics {
+            name: field.name().clone(),
+            data_type: field.data_type().to_string(),
+            null_count: row_count - non_null,
+            min,
+            max,
+            distinct_count,
+            histogram,
+        });
+    }
+
+    Ok(TableStatistics {
+        table: reference.to_string(),
+        row_count,
+        columns,
+    })
+}
+
+/// Counts the `values` non-null values of `column` between `min` and `max`
+/// in [`HISTOGRAM_BUCKETS`] buckets of equal width.
+async fn histogram(
+    ctx: &SessionContext,
+    table: &str,
+    column: &str,
+    min: f64,
+    max: f64,
+    values: u64,
+) -> AppResult<Vec<HistogramBucket>> {
+    if max <= min {
+        return Ok(vec![HistogramBucket {
+            lower: min,
+            upper: max,
+            count: values,
+        }]);
+    }
+
+    let width = (max - min) / HISTOGRAM_BUCKETS as f64;
+    let sql = format!(
+        "SELECT bucket, count(*) FROM (\
+            SELECT CAST(floor((CAST({column} AS DOUBLE) - CAST('{min:?}' AS DOUBLE)) / CAST('{width:?}' AS DOUBLE)) AS BIGINT) AS bucket \
+            FROM {table} WHERE {column} IS NOT NULL\
+        ) GROUP BY bucket"
+    );
+    let mut counts = vec![0u64; HISTOGRAM_BUCKETS];
+    for batch in ctx.sql(&sql).await?.collect().await? {
+        let buckets = arrow::compute::cast(batch.column(0), &DataType::Int64).map_err(invalid)?;
+        let buckets = buckets
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .ok_or_else(|| AppError::InternalError("Histogram buckets are not integers".to_string()))?;
+        for (bucket, count) in buckets.iter().zip(count_values(batch.column(1))?) {
+            // The maximum falls on the upper bound of the last bucket
+            let index = bucket.unwrap_or(0).clamp(0, HISTOGRAM_BUCKETS as i64 - 1) as usize;
+            counts[index] += count;
+        }
+    }
+
+    Ok(counts
+        .into_iter()
+        .enumerate()
+        .map(|(index, count)| HistogramBucket {
+            lower: min + width * index as f64,
+            upper: match index + 1 == HISTOGRAM_BUCKETS {
+                true => max,
+                false => min + width * (index + 1) as f64,
+            },
+            count,
+        })
+        .collect())
+}
+
+/// A registered table reporting the statistics of its latest analysis, so
+/// the planner can order joins and estimate filters with them.
+pub struct AnalyzedTable {
+    inner: Arc<dyn TableProvider>,
+    statistics: Statistics,
+}
+
+impl AnalyzedTable {
+    pub fn new(inner: Arc<dyn TableProvider>, statistics: &TableStatistics) -> Self {
+        let inner = unwrap_analyzed(inner);
+        let statistics = statistics.to_statistics(&inner.schema());
+        AnalyzedTable { inner, statistics }
+    }
+}
+
+/// The table behind an [`AnalyzedTable`], or `table` itself.
+pub fn unwrap_analyzed(table: Arc<dyn TableProvider>) -> Arc<dyn TableProvider> {
+    match table.as_any().downcast_ref::<AnalyzedTable>() {
+        Some(analyzed) => analyzed.inner.clone(),
+        None => table,
+    }
+}
+
+#[async_trait]
+impl TableProvider for AnalyzedTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    fn constraints(&self) -> Option<&Constraints> {
+        self.inner.constraints()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.inner.table_type()
+    }
+
+    fn get_table_definition(&self) -> Option<&str> {
+        self.inner.get_table_definition()
+    }
+
+    fn get_logical_plan(&self) -> Option<&LogicalPlan> {
+        self.inner.get_logical_plan()
+    }
+
+    fn get_column_default(&self, column: &str) -> Option<&Expr> {
+        self.inner.get_column_default(column)
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let input = self.inner.scan(state, projection, filters, limit).await?;
+        // The analyzed rows describe the whole table, not what is left after
+        // filters the source applies itself; no filter above corrects for those
+        let filter_refs: Vec<&Expr> = filters.iter().collect();
+        let filtered_by_source = self
+            .inner
+            .supports_filters_pushdown(&filter_refs)?
+            .contains(&TableProviderFilterPushDown::Exact);
+        if filtered_by_source {
+            return Ok(input);
+        }
+        let mut statistics = self.statistics.clone();
+        if let Some(indices) = projection {
+            statistics.column_statistics = indices
+                .iter()
+                .map(|index| self.statistics.column_statistics[*index].clone())
+                .collect();
+        }
+        if let Some(limit) = limit {
+            statistics.num_rows = statistics.num_rows.map(|rows| rows.min(limit));
+        }
+        Ok(Arc::new(AnalyzedExec { input, statistics }))
+    }
+
+    fn supports_filters_pushdown(&self, filters: &[&Expr]) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        self.inner.supports_filters_pushdown(filters)
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        Some(self.statistics.clone())
+    }
+
+    async fn insert_into(
+        &self,
+        state: &SessionState,
+        input: Arc<dyn ExecutionPlan>,
+        overwrite: bool,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        self.inner.insert_into(state, input, overwrite).await
+    }
+}
+
+/// Passes the scan of an [`AnalyzedTable`] through, reporting the analyzed
+/// statistics unless the scan knows its exact row count.
+#[derive(Debug)]
+struct AnalyzedExec {
+    input: Arc<dyn ExecutionPlan>,
+    statistics: Statistics,
+}
+
+impl DisplayAs for AnalyzedExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "AnalyzedExec: rows={:?}", self.statistics.num_rows)
+    }
+}
+
+impl ExecutionPlan for AnalyzedExec {
+    fn name(&self) -> &str {
+        "AnalyzedExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        self.input.properties()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(AnalyzedExec {
+            input: children.remove(0),
+            statistics: self.statistics.clone(),
+        }))
+    }
+
+    fn execute(&self, partition: usize, context: Arc<TaskContext>) -> DataFusionResult<SendableRecordBatchStream> {
+        self.input.execute(partition, context)
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        let input = self.input.statistics()?;
+        let fits = self.statistics.column_statistics.len() == self.schema().fields().len();
+        if input.num_rows.is_exact().unwrap_or(false) || !fits {
+            return Ok(input);
+        }
+        Ok(self.statistics.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::physical_plan::joins::HashJoinExec;
+
+    #[test]
+    fn test_to_statistics() {
+        let table = TableStatistics {
+            table: "datafusion.public.orders".to_string(),
+            row_count: 100,
+            columns: vec![ColumnStatistics {
+                name: "amount".to_string(),
+                data_type: "Float64".to_string(),
+                null_count: 5,
+                min: Some("1.5".to_string()),
+                max: Some("99".to_string()),
+                distinct_count: Some(40),
+                histogram: None,
+            }],
+        };
+        let schema = Schema::new(vec![
+            arrow::datatypes::Field::new("id", DataType::Int64, false),
+            arrow::datatypes::Field::new("amount", DataType::Float64, true),
+        ]);
+        let statistics = table.to_statistics(&schema);
+        assert_eq!(statistics.num_rows, Precision::Inexact(100));
+        assert_eq!(statistics.column_statistics[0], DataFusionColumnStatistics::new_unknown());
+        let amount = &statistics.column_statistics[1];
+        assert_eq!(amount.min_value, Precision::Inexact(ScalarValue::Float64(Some(1.5))));
+        assert_eq!(amount.max_value, Precision::Inexact(ScalarValue::Float64(Some(99.0))));
+        assert_eq!(amount.distinct_count, Precision::Inexact(40));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_and_plan() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("orders.csv");
+        std::fs::write(&path, "id,customer,amount\n1,ana,10.0\n2,bo,20.0\n3,ana,\n4,cy,110.0\n").unwrap();
+
+        // CSV scans do not know their row count, so the analyzed one is used
+        let ctx = SessionContext::new_with_config(
+            datafusion::execution::context::SessionConfig::new().with_target_partitions(1),
+        );
+        ctx.register_csv("orders", path.to_str().unwrap(), Default::default())
+            .await
+            .unwrap();
+        ctx.sql("CREATE TABLE customers (name VARCHAR) AS VALUES ('ana'), ('bo')")
+            .await
+            .unwrap();
+
+        let reference = ResolvedTableReference {
+            catalog: "datafusion".into(),
+            schema: "public".into(),
+            table: "orders".into(),
+        };
+        let orders = ctx.table_provider("orders").await.unwrap();
+        let statistics = analyze_table(&ctx, &reference, &orders.schema()).await.unwrap();
+        assert_eq!(statistics.row_count, 4);
+        let customer = &statistics.columns[1];
+        assert_eq!((customer.min.as_deref(), customer.max.as_deref()), (Some("ana"), Some("cy")));
+        assert_eq!(customer.distinct_count, Some(3));
+        let amount = &statistics.columns[2];
+        assert_eq!(amount.null_count, 1);
+        let histogram = amount.histogram.as_ref().unwrap();
+        assert_eq!(histogram.len(), HISTOGRAM_BUCKETS);
+        assert_eq!((histogram[0].lower, histogram[0].count), (10.0, 1));
+        assert_eq!(histogram[1].count, 1);
+        assert_eq!((histogram[9].upper, histogram[9].count), (110.0, 1));
+        assert_eq!(histogram.iter().map(|bucket| bucket.count).sum::<u64>(), 3);
+
+        // The scan reports the analyzed row count, ordering the join by it
+        ctx.deregister_table("orders").unwrap();
+        ctx.register_table("orders", Arc::new(AnalyzedTable::new(orders, &statistics)))
+            .unwrap();
+        let plan = ctx
+            .sql("SELECT * FROM orders JOIN customers ON orders.customer = customers.name")
+            .await
+            .unwrap()
+            .create_physical_plan()
+            .await
+            .unwrap();
+        let mut join = plan.clone();
+        while join.as_any().downcast_ref::<HashJoinExec>().is_none() {
+            join = join.children()[0].clone();
+        }
+        let rows: Vec<Precision<usize>> = join
+            .children()
+            .iter()
+            .map(|child| child.statistics().unwrap().num_rows)
+            .collect();
+        assert!(rows.contains(&Precision::Inexact(4)));
+    }
+
+    /// A table claiming to apply every filter itself, like the SQL adapters.
+    struct FilteringTable(Arc<dyn TableProvider>);
+
+    #[async_trait]
+    impl TableProvider for FilteringTable {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn schema(&self) -> SchemaRef {
+            self.0.schema()
+        }
+
+        fn table_type(&self) -> TableType {
+            TableType::Base
+        }
+
+        fn supports_filters_pushdown(&self, filters: &[&Expr]) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+            Ok(vec![TableProviderFilterPushDown::Exact; filters.len()])
+        }
+
+        async fn scan(
+            &self,
+            state: &SessionState,
+            projection: Option<&Vec<usize>>,
+            _filters: &[Expr],
+            limit: Option<usize>,
+        ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+            self.0.scan(state, projection, &[], limit).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filtered_scan_keeps_source_statistics() {
+        let ctx = SessionContext::new();
+        ctx.sql("CREATE TABLE orders (id BIGINT) AS VALUES (1), (2), (3)")
+            .await
+            .unwrap();
+        let orders = ctx.table_provider("orders").await.unwrap();
+        let statistics = TableStatistics {
+            table: "datafusion.public.orders".to_string(),
+            row_count: 1000,
+            columns: vec![],
+        };
+        let table = AnalyzedTable::new(Arc::new(FilteringTable(orders)), &statistics);
+        let state = ctx.state();
+
+        let scan = table.scan(&state, None, &[], None).await.unwrap();
+        assert!(scan.as_any().downcast_ref::<AnalyzedExec>().is_some());
+
+        // The source filtered the rows, so the table's row count no longer applies
+        let filter = datafusion::prelude::col("id").eq(datafusion::prelude::lit(2i64));
+        let scan = table.scan(&state, None, &[filter], None).await.unwrap();
+        assert!(scan.as_any().downcast_ref::<AnalyzedExec>().is_none());
+    }
+}
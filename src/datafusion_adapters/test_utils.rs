@@ -0,0 +1,29 @@
+//! Helpers shared by the adapter tests.
+
+use super::data_source::{DataSourceConfig, DataSourceType};
+use arrow::array::Int64Array;
+use datafusion::prelude::SessionContext;
+
+/// A config for a data source whose id and name are both `name`.
+pub fn source_config(
+    r#type: DataSourceType,
+    name: &str,
+    connection_string: impl Into<String>,
+    options: &[(&str, &str)],
+) -> DataSourceConfig {
+    DataSourceConfig {
+        id: name.to_string(),
+        name: name.to_string(),
+        ..DataSourceConfig::for_test(
+            r#type,
+            connection_string,
+            options.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        )
+    }
+}
+
+/// The count returned by a query such as `SELECT count(*) FROM t`.
+pub async fn count(ctx: &SessionContext, sql: &str) -> i64 {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    batches[0].column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0)
+}
@@ -0,0 +1,67 @@
+use super::catalog::{belongs_to_source, scanned_tables, SourceTables};
+use super::data_source::DataSourceConfig;
+use super::query_engine::read_only_sql_options;
+use crate::utils::{AppError, AppResult};
+use datafusion::datasource::ViewTable;
+use datafusion::execution::context::SessionContext;
+use std::sync::Arc;
+
+/// Plans the query of a `View` data source against the tables registered in
+/// `ctx`, as a view that reads them at query time.
+///
+/// `connection_string` must be a single `SELECT` query; DDL, DML and other
+/// statements are rejected, as is a query reading the view itself.
+pub async fn load_table(ctx: &SessionContext, config: &DataSourceConfig) -> AppResult<SourceTables> {
+    if !config.options.is_empty() {
+        return Err(AppError::ValidationError("View data sources take no options".to_string()));
+    }
+    let query = config.connection_string.trim();
+    if query.is_empty() {
+        return Err(AppError::ValidationError("A view needs a SQL query".to_string()));
+    }
+
+    let plan = ctx
+        .sql_with_options(query, read_only_sql_options())
+        .await
+        .map_err(|e| AppError::ValidationError(format!("Invalid view query: {}", e)))?
+        .into_unoptimized_plan();
+    if scanned_tables(&plan).iter().any(|table| belongs_to_source(table, &config.name)) {
+        return Err(AppError::ValidationError(format!("View {} cannot read itself", config.name)));
+    }
+
+    let view = ViewTable::try_new(plan, Some(query.to_string()))?;
+    Ok(SourceTables::Table(Arc::new(view)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafusion_adapters::data_source::DataSourceType;
+    use crate::datafusion_adapters::test_utils::{count, source_config};
+    use datafusion::datasource::TableProvider;
+
+    fn view_config(name: &str, query: &str) -> DataSourceConfig {
+        source_config(DataSourceType::View, name, query, &[])
+    }
+
+    #[tokio::test]
+    async fn test_load_view() {
+        let ctx = SessionContext::new();
+        ctx.sql("CREATE TABLE orders (id BIGINT, amount DOUBLE) AS VALUES (1, 2.5), (2, -1.0)")
+            .await
+            .unwrap();
+
+        let config = view_config("paid_orders", "SELECT id, amount FROM orders WHERE amount > 0");
+        let SourceTables::Table(view) = load_table(&ctx, &config).await.unwrap() else {
+            panic!("expected a single table");
+        };
+        assert_eq!(view.schema().fields().len(), 2);
+
+        ctx.register_table("paid_orders", view).unwrap();
+        assert_eq!(count(&ctx, "SELECT count(*) FROM paid_orders").await, 1);
+
+        for query in ["DROP TABLE orders", "SELECT * FROM missing", "", "SELECT * FROM paid_orders"] {
+            assert!(load_table(&ctx, &view_config("paid_orders", query)).await.is_err());
+        }
+    }
+}
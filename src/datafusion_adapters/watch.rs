@@ -0,0 +1,266 @@
+use super::data_source::DataSourceConfig;
+use crate::utils::{AppError, AppResult};
+use object_store::ObjectMeta;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex, RwLock};
+
+const DEFAULT_WATCH_INTERVAL_SECONDS: u64 = 30;
+/// Events kept for `GET /api/data-sources/{id}/files`, across all sources.
+const MAX_RECENT_EVENTS: usize = 1000;
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// How often a file source's directory is listed again, if it is watched.
+///
+/// Watching is enabled with `options["watch"] = "true"`; the interval comes
+/// from `options["watch_interval_seconds"]` (default 30).
+pub fn watch_interval(options: &HashMap<String, String>) -> AppResult<Option<Duration>> {
+    match options.get("watch").map(|v| v.to_lowercase()).as_deref() {
+        None | Some("false") => {
+            if options.contains_key("watch_interval_seconds") {
+                return Err(AppError::ValidationError(
+                    "watch_interval_seconds needs options.watch = true".to_string(),
+                ));
+            }
+            Ok(None)
+        }
+        Some("true") => {
+            let seconds = match options.get("watch_interval_seconds") {
+                Some(value) => value.parse::<u64>().ok().filter(|s| *s > 0).ok_or_else(|| {
+                    AppError::ValidationError(format!("Invalid watch_interval_seconds: {}", value))
+                })?,
+                None => DEFAULT_WATCH_INTERVAL_SECONDS,
+            };
+            Ok(Some(Duration::from_secs(seconds)))
+        }
+        Some(other) => Err(AppError::ValidationError(format!(
+            "Option watch must be true or false, got {}",
+            other
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileEventKind {
+    Added,
+    Removed,
+    /// The file's size or modification time changed.
+    Modified,
+}
+
+/// A file that appeared, disappeared or changed behind a watched data source.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEvent {
+    pub data_source_id: String,
+    pub data_source: String,
+    pub kind: FileEventKind,
+    pub path: String,
+    pub size: u64,
+    pub detected_at: chrono::NaiveDateTime,
+}
+
+/// Files and bytes behind a watched data source as of its last listing.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileInventory {
+    pub files: usize,
+    pub bytes: u64,
+    pub scanned_at: chrono::NaiveDateTime,
+}
+
+/// Size and modification time (epoch millis) by path.
+type Listing = HashMap<String, (u64, i64)>;
+
+/// Changes from `previous` to `current`, sorted by path.
+fn diff(previous: &Listing, current: &Listing) -> Vec<(FileEventKind, String, u64)> {
+    let mut changes: Vec<_> = current
+        .iter()
+        .filter_map(|(path, &(size, modified))| match previous.get(path) {
+            None => Some((FileEventKind::Added, path.clone(), size)),
+            Some(&old) if old != (size, modified) => Some((FileEventKind::Modified, path.clone(), size)),
+            Some(_) => None,
+        })
+        .chain(
+            previous
+                .iter()
+                .filter(|(path, _)| !current.contains_key(*path))
+                .map(|(path, &(size, _))| (FileEventKind::Removed, path.clone(), size)),
+        )
+        .collect();
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+    changes
+}
+
+struct WatchState {
+    listing: Listing,
+    inventory: FileInventory,
+}
+
+/// Listings of watched file sources and the changes found between them.
+///
+/// Events are broadcast to subscribers and the most recent ones are kept
+/// for the API.
+pub struct FileWatcher {
+    states: RwLock<HashMap<String, WatchState>>,
+    next_scans: RwLock<HashMap<String, Instant>>,
+    recent: Mutex<VecDeque<FileEvent>>,
+    events: broadcast::Sender<FileEvent>,
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        FileWatcher {
+            states: RwLock::new(HashMap::new()),
+            next_scans: RwLock::new(HashMap::new()),
+            recent: Mutex::new(VecDeque::new()),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Receives every file event from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<FileEvent> {
+        self.events.subscribe()
+    }
+
+    /// Whether the source `id` has never been listed or is due again.
+    pub async fn is_due(&self, id: &str) -> bool {
+        self.next_scans
+            .read()
+            .await
+            .get(id)
+            .map(|next_scan| *next_scan <= Instant::now())
+            .unwrap_or(true)
+    }
+
+    /// Sets when `id` is due next, whether or not the current listing succeeds.
+    pub async fn schedule(&self, id: &str, interval: Duration) {
+        self.next_scans
+            .write()
+            .await
+            .insert(id.to_string(), Instant::now() + interval);
+    }
+
+    /// Stores a new listing of `config` and returns what changed since the
+    /// previous one. The first listing of a source only sets the baseline.
+    pub async fn record(&self, config: &DataSourceConfig, files: &[ObjectMeta]) -> Vec<FileEvent> {
+        let listing: Listing = files
+            .iter()
+            .map(|f| (f.location.to_string(), (f.size as u64, f.last_modified.timestamp_millis())))
+            .collect();
+        let now = chrono::Utc::now().naive_utc();
+        let inventory = FileInventory {
+            files: listing.len(),
+            bytes: listing.values().map(|(size, _)| size).sum(),
+            scanned_at: now,
+        };
+
+        let mut states = self.states.write().await;
+        let changes = states.get(&config.id).map(|previous| diff(&previous.listing, &listing));
+        states.insert(config.id.clone(), WatchState { listing, inventory });
+        drop(states);
+
+        let Some(changes) = changes else {
+            return vec![];
+        };
+        let events: Vec<FileEvent> = changes
+            .into_iter()
+            .map(|(kind, path, size)| FileEvent {
+                data_source_id: config.id.clone(),
+                data_source: config.name.clone(),
+                kind,
+                path,
+                size,
+                detected_at: now,
+            })
+            .collect();
+
+        let mut recent = self.recent.lock().await;
+        for event in &events {
+            // Sending only fails when nobody is subscribed
+            let _ = self.events.send(event.clone());
+            recent.push_back(event.clone());
+        }
+        while recent.len() > MAX_RECENT_EVENTS {
+            recent.pop_front();
+        }
+        events
+    }
+
+    pub async fn inventory(&self, id: &str) -> Option<FileInventory> {
+        self.states.read().await.get(id).map(|state| state.inventory.clone())
+    }
+
+    /// Recent events of the source `id`, oldest first.
+    pub async fn events(&self, id: &str) -> Vec<FileEvent> {
+        self.recent
+            .lock()
+            .await
+            .iter()
+            .filter(|event| event.data_source_id == id)
+            .cloned()
+            .collect()
+    }
+
+    /// Forgets the listing of `id`, so its next listing is a new baseline.
+    pub async fn remove(&self, id: &str) {
+        self.states.write().await.remove(id);
+        self.next_scans.write().await.remove(id);
+    }
+
+    /// Forgets every source not in `ids`.
+    pub async fn retain(&self, ids: &[String]) {
+        self.states.write().await.retain(|id, _| ids.contains(id));
+        self.next_scans.write().await.retain(|id, _| ids.contains(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_interval() {
+        let options = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert_eq!(watch_interval(&options(&[])).unwrap(), None);
+        assert_eq!(
+            watch_interval(&options(&[("watch", "true")])).unwrap(),
+            Some(Duration::from_secs(DEFAULT_WATCH_INTERVAL_SECONDS))
+        );
+        assert_eq!(
+            watch_interval(&options(&[("watch", "true"), ("watch_interval_seconds", "5")])).unwrap(),
+            Some(Duration::from_secs(5))
+        );
+        assert!(watch_interval(&options(&[("watch", "true"), ("watch_interval_seconds", "0")])).is_err());
+        assert!(watch_interval(&options(&[("watch_interval_seconds", "5")])).is_err());
+        assert!(watch_interval(&options(&[("watch", "inotify")])).is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let previous: Listing = [("a.csv", (10, 1)), ("b.csv", (20, 1)), ("c.csv", (30, 1))]
+            .into_iter()
+            .map(|(p, v)| (p.to_string(), v))
+            .collect();
+        let current: Listing = [("a.csv", (10, 1)), ("b.csv", (25, 2)), ("d.csv", (40, 3))]
+            .into_iter()
+            .map(|(p, v)| (p.to_string(), v))
+            .collect();
+        assert_eq!(
+            diff(&previous, &current),
+            vec![
+                (FileEventKind::Modified, "b.csv".to_string(), 25),
+                (FileEventKind::Removed, "c.csv".to_string(), 30),
+                (FileEventKind::Added, "d.csv".to_string(), 40),
+            ]
+        );
+    }
+}
@@ -1,7 +1,16 @@
-use crate::datafusion_adapters::data_source::{DataSourceConfig, DataSourceManager, DataSourceType};
-use crate::utils::{success_response, AppResult};
+use crate::datafusion_adapters::data_source::{
+    ConnectionTestResult, DataSourceConfig, DataSourceDependencies, DataSourceFiles, DataSourceManager,
+    DataSourcePreview, DataSourceStatus, DataSourceType,
+};
+use crate::datafusion_adapters::memory::{ColumnDefinition, MemoryTableSize, RowFormat};
+use crate::datafusion_adapters::statistics::DataSourceStatistics;
+use crate::datafusion_adapters::watch::FileInventory;
+use crate::utils::secrets::mask_options;
+use crate::utils::{success_response, AppError, AppResult};
 use axum::{
-    extract::{Json, Path, State},
+    body::Bytes,
+    extract::{Json, Path, Query, State},
+    http::{header, HeaderMap},
     response::Json as AxumJson,
     routing::{delete, get, post, put},
     Router,
@@ -15,6 +24,46 @@ pub struct CreateDataSourceRequest {
     pub r#type: DataSourceType,
     pub connection_string: String,
     pub options: std::collections::HashMap<String, String>,
+    /// Column schema, required for `Memory` data sources and optional for file sources.
+    #[serde(default)]
+    pub columns: Option<Vec<ColumnDefinition>>,
+}
+
+impl CreateDataSourceRequest {
+    /// A new, untested data source `id` created now.
+    fn into_config(self, id: String) -> AppResult<DataSourceConfig> {
+        let now = chrono::Utc::now().naive_utc();
+        Ok(DataSourceConfig {
+            id,
+            columns: columns_json(&self.columns)?,
+            name: self.name,
+            r#type: self.r#type,
+            connection_string: self.connection_string,
+            options: self.options,
+            schema: None,
+            created_at: now,
+            updated_at: now,
+            status: DataSourceStatus::Unknown,
+            last_checked_at: None,
+            last_error: None,
+            secrets: None,
+        })
+    }
+}
+
+const DEFAULT_PREVIEW_ROWS: usize = 10;
+const MAX_PREVIEW_ROWS: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct PreviewParams {
+    pub limit: Option<usize>,
+}
+
+fn columns_json(columns: &Option<Vec<ColumnDefinition>>) -> AppResult<Option<String>> {
+    columns
+        .as_ref()
+        .map(|c| serde_json::to_string(c).map_err(|e| AppError::InternalError(e.to_string())))
+        .transpose()
 }
 
 #[derive(Serialize)]
@@ -25,21 +74,40 @@ pub struct DataSourceResponse {
     pub connection_string: String,
     pub options: std::collections::HashMap<String, String>,
     pub schema: Option<String>,
+    /// Columns given when the source was created or updated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<String>,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub status: DataSourceStatus,
+    pub last_checked_at: Option<chrono::NaiveDateTime>,
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<MemoryTableSize>,
+    /// Files and bytes behind a watched file source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<FileInventory>,
 }
 
+/// Credentials are always masked, including those of sources stored before
+/// encryption was enabled.
 impl From<DataSourceConfig> for DataSourceResponse {
     fn from(config: DataSourceConfig) -> Self {
         DataSourceResponse {
             id: config.id,
             name: config.name,
             r#type: config.r#type,
-            connection_string: config.connection_string,
-            options: config.options,
+            connection_string: config.masked_connection_string(),
+            options: mask_options(&config.options),
             schema: config.schema,
+            columns: config.columns,
             created_at: config.created_at,
             updated_at: config.updated_at,
+            status: config.status,
+            last_checked_at: config.last_checked_at,
+            last_error: config.last_error,
+            size: None,
+            files: None,
         }
     }
 }
@@ -49,25 +117,29 @@ pub async fn create_data_source(
     Json(request): Json<CreateDataSourceRequest>,
 ) -> AppResult<AxumJson<DataSourceResponse>> {
     let id = uuid::Uuid::new_v4().to_string();
-    let now = chrono::Utc::now().naive_utc();
-    
-    let config = DataSourceConfig {
-        id: id.clone(),
-        name: request.name,
-        r#type: request.r#type,
-        connection_string: request.connection_string,
-        options: request.options,
-        schema: None,
-        created_at: now,
-        updated_at: now,
-    };
-
-    data_source_manager.add_data_source(config.clone()).await?;
-    
-    // Register the data source with the query engine
-    data_source_manager.register_data_source(&id).await?;
+    let config = request.into_config(id.clone())?;
 
-    Ok(AxumJson(config.into()))
+    // Stores the data source and registers it with the query engine
+    data_source_manager.create_data_source(config).await?;
+
+    // Registration stores the inferred schema
+    let config = data_source_manager.get_data_source(&id).await?;
+    let files = data_source_manager.file_inventory(&config).await;
+    let mut response = DataSourceResponse::from(config);
+    response.files = files;
+    Ok(AxumJson(response))
+}
+
+pub async fn preview_data_source(
+    State(data_source_manager): State<Arc<DataSourceManager>>,
+    Query(params): Query<PreviewParams>,
+    Json(request): Json<CreateDataSourceRequest>,
+) -> AppResult<AxumJson<DataSourcePreview>> {
+    let limit = params.limit.unwrap_or(DEFAULT_PREVIEW_ROWS).min(MAX_PREVIEW_ROWS);
+    let config = request.into_config(uuid::Uuid::new_v4().to_string())?;
+
+    let preview = data_source_manager.preview_data_source(config, limit).await?;
+    Ok(AxumJson(preview))
 }
 
 pub async fn get_data_source(
@@ -83,34 +155,31 @@ pub async fn update_data_source(
     Path(id): Path<String>,
     Json(request): Json<CreateDataSourceRequest>,
 ) -> AppResult<AxumJson<DataSourceResponse>> {
-    let now = chrono::Utc::now().naive_utc();
-    let config = DataSourceConfig {
-        id: id.clone(),
-        name: request.name,
-        r#type: request.r#type,
-        connection_string: request.connection_string,
-        options: request.options,
-        schema: None,
-        created_at: now, // Keep original creation time
-        updated_at: now,
-    };
-
-    data_source_manager.update_data_source(id, config.clone()).await?;
-    
-    // Re-register the data source with the query engine
-    data_source_manager.register_data_source(&config.id).await?;
+    // `created_at` is replaced by the original creation time
+    let config = request.into_config(id.clone())?;
 
+    // Swaps the registered tables, handling renames
+    let config = data_source_manager.update_data_source(&id, config).await?;
     Ok(AxumJson(config.into()))
 }
 
+#[derive(Deserialize)]
+pub struct DeleteParams {
+    /// Also drop views that read the data source.
+    #[serde(default)]
+    pub cascade: bool,
+}
+
 pub async fn delete_data_source(
     State(data_source_manager): State<Arc<DataSourceManager>>,
     Path(id): Path<String>,
+    Query(params): Query<DeleteParams>,
 ) -> AppResult<AxumJson<serde_json::Value>> {
-    data_source_manager.delete_data_source(&id).await?;
+    let dropped_views = data_source_manager.delete_data_source(&id, params.cascade).await?;
     Ok(AxumJson(serde_json::json!({
         "success": true,
-        "message": "Data source deleted successfully"
+        "message": "Data source deleted successfully",
+        "dropped_views": dropped_views
     })))
 }
 
@@ -118,15 +187,97 @@ pub async fn list_data_sources(
     State(data_source_manager): State<Arc<DataSourceManager>>,
 ) -> AppResult<AxumJson<Vec<DataSourceResponse>>> {
     let configs = data_source_manager.list_data_sources().await?;
-    let responses: Vec<DataSourceResponse> = configs.into_iter().map(|c| c.into()).collect();
+    let mut responses = Vec::with_capacity(configs.len());
+    for config in configs {
+        let size = data_source_manager.memory_table_size(&config).await;
+        let files = data_source_manager.file_inventory(&config).await;
+        let mut response = DataSourceResponse::from(config);
+        response.size = size;
+        response.files = files;
+        responses.push(response);
+    }
     Ok(AxumJson(responses))
 }
 
+pub async fn test_data_source(
+    State(data_source_manager): State<Arc<DataSourceManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<ConnectionTestResult>> {
+    let result = data_source_manager.test_data_source(&id).await?;
+    Ok(AxumJson(result))
+}
+
+pub async fn get_data_source_dependencies(
+    State(data_source_manager): State<Arc<DataSourceManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<DataSourceDependencies>> {
+    let dependencies = data_source_manager.data_source_dependencies(&id).await?;
+    Ok(AxumJson(dependencies))
+}
+
+pub async fn get_data_source_files(
+    State(data_source_manager): State<Arc<DataSourceManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<DataSourceFiles>> {
+    let files = data_source_manager.data_source_files(&id).await?;
+    Ok(AxumJson(files))
+}
+
+pub async fn analyze_data_source(
+    State(data_source_manager): State<Arc<DataSourceManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<DataSourceStatistics>> {
+    let statistics = data_source_manager.analyze_data_source(&id).await?;
+    Ok(AxumJson(statistics))
+}
+
+pub async fn get_data_source_statistics(
+    State(data_source_manager): State<Arc<DataSourceManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<DataSourceStatistics>> {
+    let statistics = data_source_manager.data_source_statistics(&id).await?;
+    Ok(AxumJson(statistics))
+}
+
+pub async fn append_rows(
+    State(data_source_manager): State<Arc<DataSourceManager>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<AxumJson<MemoryTableSize>> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json");
+    let format = RowFormat::from_content_type(content_type)?;
+    let size = data_source_manager.append_rows(&id, format, &body).await?;
+    Ok(AxumJson(size))
+}
+
+pub async fn truncate_rows(
+    State(data_source_manager): State<Arc<DataSourceManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<serde_json::Value>> {
+    data_source_manager.truncate_rows(&id).await?;
+    Ok(AxumJson(serde_json::json!({
+        "success": true,
+        "message": "Memory table truncated successfully"
+    })))
+}
+
 pub fn data_source_routes() -> Router {
     Router::new()
         .route("/api/data-sources", post(create_data_source))
         .route("/api/data-sources", get(list_data_sources))
+        .route("/api/data-sources/preview", post(preview_data_source))
         .route("/api/data-sources/:id", get(get_data_source))
         .route("/api/data-sources/:id", put(update_data_source))
         .route("/api/data-sources/:id", delete(delete_data_source))
+        .route("/api/data-sources/:id/test", post(test_data_source))
+        .route("/api/data-sources/:id/dependencies", get(get_data_source_dependencies))
+        .route("/api/data-sources/:id/files", get(get_data_source_files))
+        .route("/api/data-sources/:id/analyze", post(analyze_data_source))
+        .route("/api/data-sources/:id/stats", get(get_data_source_statistics))
+        .route("/api/data-sources/:id/rows", post(append_rows))
+        .route("/api/data-sources/:id/rows", delete(truncate_rows))
 }
\ No newline at end of file
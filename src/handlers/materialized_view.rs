@@ -0,0 +1,100 @@
+use crate::datafusion_adapters::materialized_view::{MaterializedView, MaterializedViewManager};
+use crate::utils::AppResult;
+use axum::{
+    extract::{Json, Path, State},
+    response::Json as AxumJson,
+    routing::{delete, get, post, put},
+    Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct MaterializedViewRequest {
+    pub name: String,
+    pub sql: String,
+    /// Cron expression, e.g. `0 * * * *` for hourly refreshes.
+    #[serde(default)]
+    pub schedule: Option<String>,
+}
+
+fn materialized_view(id: String, request: MaterializedViewRequest) -> MaterializedView {
+    let now = chrono::Utc::now().naive_utc();
+    MaterializedView {
+        id,
+        name: request.name,
+        sql: request.sql,
+        schedule: request.schedule.filter(|schedule| !schedule.trim().is_empty()),
+        location: None,
+        last_refreshed_at: None,
+        row_count: None,
+        refresh_duration_ms: None,
+        last_error: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+pub async fn create_materialized_view(
+    State(manager): State<Arc<MaterializedViewManager>>,
+    Json(request): Json<MaterializedViewRequest>,
+) -> AppResult<AxumJson<MaterializedView>> {
+    let view = materialized_view(uuid::Uuid::new_v4().to_string(), request);
+    // Runs the first refresh, so the table is queryable right away
+    let view = manager.create_materialized_view(view).await?;
+    Ok(AxumJson(view))
+}
+
+pub async fn list_materialized_views(
+    State(manager): State<Arc<MaterializedViewManager>>,
+) -> AppResult<AxumJson<Vec<MaterializedView>>> {
+    let views = manager.list_materialized_views().await?;
+    Ok(AxumJson(views))
+}
+
+pub async fn get_materialized_view(
+    State(manager): State<Arc<MaterializedViewManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<MaterializedView>> {
+    let view = manager.get_materialized_view(&id).await?;
+    Ok(AxumJson(view))
+}
+
+pub async fn update_materialized_view(
+    State(manager): State<Arc<MaterializedViewManager>>,
+    Path(id): Path<String>,
+    Json(request): Json<MaterializedViewRequest>,
+) -> AppResult<AxumJson<MaterializedView>> {
+    let view = materialized_view(id.clone(), request);
+    let view = manager.update_materialized_view(&id, view).await?;
+    Ok(AxumJson(view))
+}
+
+pub async fn delete_materialized_view(
+    State(manager): State<Arc<MaterializedViewManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<serde_json::Value>> {
+    manager.delete_materialized_view(&id).await?;
+    Ok(AxumJson(serde_json::json!({
+        "success": true,
+        "message": "Materialized view deleted successfully"
+    })))
+}
+
+pub async fn refresh_materialized_view(
+    State(manager): State<Arc<MaterializedViewManager>>,
+    Path(id): Path<String>,
+) -> AppResult<AxumJson<MaterializedView>> {
+    let view = manager.refresh(&id).await?;
+    Ok(AxumJson(view))
+}
+
+pub fn materialized_view_routes() -> Router {
+    Router::new()
+        .route("/api/materialized-views", post(create_materialized_view))
+        .route("/api/materialized-views", get(list_materialized_views))
+        .route("/api/materialized-views/:id", get(get_materialized_view))
+        .route("/api/materialized-views/:id", put(update_materialized_view))
+        .route("/api/materialized-views/:id", delete(delete_materialized_view))
+        .route("/api/materialized-views/:id/refresh", post(refresh_materialized_view))
+}
@@ -2,10 +2,12 @@ pub mod auth;
 pub mod casbin;
 pub mod data_source;
 pub mod health;
+pub mod materialized_view;
 pub mod query;
 
 pub use auth::*;
 pub use casbin::*;
 pub use data_source::*;
 pub use health::*;
+pub use materialized_view::*;
 pub use query::*;
\ No newline at end of file
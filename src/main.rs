@@ -12,10 +12,17 @@ mod services;
 mod utils;
 
 use config::Config;
-use datafusion_adapters::{DataSourceManager, QueryEngine};
-use handlers::{auth_routes, casbin_routes, data_source_routes, health_routes, query_routes};
+use datafusion_adapters::adbc_source::AdbcDrivers;
+use datafusion_adapters::materialized_view::MaterializedViewManager;
+use datafusion_adapters::{DataSourceManager, QueryEngine, SharedCatalog};
+use handlers::{
+    auth_routes, casbin_routes, data_source_routes, health_routes, materialized_view_routes, query_routes,
+};
 use middleware::{auth::auth_middleware, cors::cors_layer};
 use services::casbin_service::CasbinService;
+use services::data_source_repository::DataSourceRepository;
+use services::materialized_view_repository::MaterializedViewRepository;
+use utils::secrets::SecretCipher;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -41,13 +48,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .expect("Failed to initialize Casbin service")
     );
 
-    // Initialize DataFusion components
-    let data_source_manager = Arc::new(DataSourceManager::new());
-    let query_engine = Arc::new(QueryEngine::new());
+    // Initialize DataFusion components; all of them share one catalog
+    let catalog = Arc::new(SharedCatalog::from_config(&config.datafusion)?);
+    let data_source_manager = Arc::new(DataSourceManager::with_repository(
+        catalog.clone(),
+        DataSourceRepository::new(pool.clone()),
+    )
+    .with_memory_table_limit(config.datafusion.memory_table_limit())
+    .with_adbc_drivers(AdbcDrivers::new(
+        config.datafusion.adbc_driver_dir.clone(),
+        config.datafusion.adbc_drivers.clone(),
+    ))
+    .with_secret_cipher(config.security.master_key.as_deref().map(SecretCipher::new).transpose()?));
+    let failures = data_source_manager.restore_data_sources().await?;
+    if !failures.is_empty() {
+        tracing::warn!("{} data source(s) could not be restored", failures.len());
+    }
+    if config.datafusion.health_check_interval_secs > 0 {
+        data_source_manager.clone().spawn_health_checker(std::time::Duration::from_secs(
+            config.datafusion.health_check_interval_secs,
+        ));
+    }
+    data_source_manager.clone().spawn_file_watcher();
+    data_source_manager.clone().spawn_statistics_scheduler();
+    let query_engine = Arc::new(QueryEngine::new(catalog.clone()));
+
+    // Materialized views read data sources, so they are restored afterwards
+    let materialized_view_manager = Arc::new(
+        MaterializedViewManager::new(
+            catalog.clone(),
+            query_engine.clone(),
+            config.datafusion.materialized_view_dir(),
+        )
+        .with_repository(MaterializedViewRepository::new(pool.clone())),
+    );
+    let failures = materialized_view_manager.restore_materialized_views().await?;
+    if !failures.is_empty() {
+        tracing::warn!("{} materialized view(s) could not be restored", failures.len());
+    }
+    materialized_view_manager.clone().spawn_scheduler();
 
     // Initialize Flight SQL server if enabled
     if config.datafusion.enable_flight_server {
-        let flight_server = datafusion_adapters::FlightSqlServer::new();
+        let flight_server = datafusion_adapters::FlightSqlServer::new(catalog.clone());
         tokio::spawn(async move {
             if let Err(e) = flight_server.start_server(config.datafusion.flight_port).await {
                 tracing::error!("Flight server error: {}", e);
@@ -65,6 +108,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Protected routes with auth middleware
         .merge(casbin_routes())
         .merge(data_source_routes())
+        .merge(materialized_view_routes())
         .merge(query_routes())
         // Add middleware
         .layer(cors_layer())
@@ -77,6 +121,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             config: Arc::new(config),
             casbin_service,
             data_source_manager,
+            materialized_view_manager,
             query_engine,
         });
 
@@ -97,5 +142,6 @@ pub struct AppState {
     pub config: Arc<Config>,
     pub casbin_service: Arc<CasbinService>,
     pub data_source_manager: Arc<DataSourceManager>,
+    pub materialized_view_manager: Arc<MaterializedViewManager>,
     pub query_engine: Arc<QueryEngine>,
 }
\ No newline at end of file
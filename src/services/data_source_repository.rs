@@ -0,0 +1,318 @@
+use crate::datafusion_adapters::data_source::DataSourceConfig;
+use crate::datafusion_adapters::statistics::{DataSourceStatistics, TableStatistics};
+use crate::utils::{AppError, AppResult};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct DataSourceRow {
+    pub id: String,
+    pub name: String,
+    #[sqlx(rename = "type")]
+    pub source_type: String,
+    pub connection_string: String,
+    pub options: Json<HashMap<String, String>>,
+    pub schema: Option<String>,
+    pub columns: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub status: String,
+    pub last_checked_at: Option<chrono::NaiveDateTime>,
+    pub last_error: Option<String>,
+    pub secrets: Option<String>,
+}
+
+impl TryFrom<DataSourceRow> for DataSourceConfig {
+    type Error = AppError;
+
+    fn try_from(row: DataSourceRow) -> Result<Self, Self::Error> {
+        Ok(DataSourceConfig {
+            id: row.id,
+            name: row.name,
+            r#type: row.source_type.parse()?,
+            connection_string: row.connection_string,
+            options: row.options.0,
+            schema: row.schema,
+            columns: row.columns,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            status: row.status.parse()?,
+            last_checked_at: row.last_checked_at,
+            last_error: row.last_error,
+            secrets: row.secrets,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct DataSourceStatisticsRow {
+    pub data_source_id: String,
+    pub tables: Json<Vec<TableStatistics>>,
+    pub duration_ms: i64,
+    pub analyzed_at: chrono::NaiveDateTime,
+}
+
+impl From<DataSourceStatisticsRow> for DataSourceStatistics {
+    fn from(row: DataSourceStatisticsRow) -> Self {
+        DataSourceStatistics {
+            data_source_id: row.data_source_id,
+            analyzed_at: row.analyzed_at,
+            duration_ms: row.duration_ms as u64,
+            tables: row.tables.0,
+        }
+    }
+}
+
+pub struct DataSourceRepository {
+    pool: PgPool,
+}
+
+impl DataSourceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        DataSourceRepository { pool }
+    }
+
+    pub async fn insert(&self, config: &DataSourceConfig) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO data_sources (
+                id, name, type, connection_string, options, schema, columns, created_at,
+                updated_at, status, last_checked_at, last_error, secrets
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(&config.id)
+        .bind(&config.name)
+        .bind(config.r#type.as_str())
+        .bind(&config.connection_string)
+        .bind(Json(&config.options))
+        .bind(&config.schema)
+        .bind(&config.columns)
+        .bind(config.created_at)
+        .bind(config.updated_at)
+        .bind(config.status.as_str())
+        .bind(config.last_checked_at)
+        .bind(&config.last_error)
+        .bind(&config.secrets)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(())
+    }
+
+    pub async fn update(&self, config: &DataSourceConfig) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE data_sources
+            SET
+                name = $2,
+                type = $3,
+                connection_string = $4,
+                options = $5,
+                schema = $6,
+                columns = $7,
+                updated_at = $8,
+                status = $9,
+                last_checked_at = $10,
+                last_error = $11,
+                secrets = $12
+            WHERE id = $1
+            "#,
+        )
+        .bind(&config.id)
+        .bind(&config.name)
+        .bind(config.r#type.as_str())
+        .bind(&config.connection_string)
+        .bind(Json(&config.options))
+        .bind(&config.schema)
+        .bind(&config.columns)
+        .bind(config.updated_at)
+        .bind(config.status.as_str())
+        .bind(config.last_checked_at)
+        .bind(&config.last_error)
+        .bind(&config.secrets)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Saves the outcome of a connection test without touching `updated_at`.
+    pub async fn update_health(&self, config: &DataSourceConfig) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE data_sources
+            SET
+                status = $2,
+                last_checked_at = $3,
+                last_error = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(&config.id)
+        .bind(config.status.as_str())
+        .bind(config.last_checked_at)
+        .bind(&config.last_error)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete(&self, id: &str) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM data_sources
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list(&self) -> AppResult<Vec<DataSourceConfig>> {
+        let rows = sqlx::query_as::<_, DataSourceRow>(
+            r#"
+            SELECT
+                id,
+                name,
+                type,
+                connection_string,
+                options,
+                schema,
+                columns,
+                created_at,
+                updated_at,
+                status,
+                last_checked_at,
+                last_error,
+                secrets
+            FROM data_sources
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        rows.into_iter().map(DataSourceConfig::try_from).collect()
+    }
+
+    /// Stores the statistics of an analysis, replacing the previous ones.
+    pub async fn save_statistics(&self, statistics: &DataSourceStatistics) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO data_source_statistics (data_source_id, tables, duration_ms, analyzed_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (data_source_id) DO UPDATE
+            SET
+                tables = EXCLUDED.tables,
+                duration_ms = EXCLUDED.duration_ms,
+                analyzed_at = EXCLUDED.analyzed_at
+            "#,
+        )
+        .bind(&statistics.data_source_id)
+        .bind(Json(&statistics.tables))
+        .bind(statistics.duration_ms as i64)
+        .bind(statistics.analyzed_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(())
+    }
+
+    pub async fn delete_statistics(&self, data_source_id: &str) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM data_source_statistics
+            WHERE data_source_id = $1
+            "#,
+        )
+        .bind(data_source_id)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list_statistics(&self) -> AppResult<Vec<DataSourceStatistics>> {
+        let rows = sqlx::query_as::<_, DataSourceStatisticsRow>(
+            r#"
+            SELECT
+                data_source_id,
+                tables,
+                duration_ms,
+                analyzed_at
+            FROM data_source_statistics
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(rows.into_iter().map(DataSourceStatistics::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafusion_adapters::data_source::DataSourceType;
+
+    #[test]
+    fn test_row_to_config() {
+        let now = chrono::Utc::now().naive_utc();
+        let row = DataSourceRow {
+            id: "source-1".to_string(),
+            name: "sales".to_string(),
+            source_type: "Parquet".to_string(),
+            connection_string: "/data/sales.parquet".to_string(),
+            options: Json(HashMap::new()),
+            schema: None,
+            columns: None,
+            created_at: now,
+            updated_at: now,
+            status: "unknown".to_string(),
+            last_checked_at: None,
+            last_error: None,
+            secrets: None,
+        };
+
+        let config = DataSourceConfig::try_from(row).unwrap();
+        assert!(matches!(config.r#type, DataSourceType::Parquet));
+        assert_eq!(config.created_at, now);
+    }
+
+    #[test]
+    fn test_row_with_unknown_type() {
+        let now = chrono::Utc::now().naive_utc();
+        let row = DataSourceRow {
+            id: "source-1".to_string(),
+            name: "sales".to_string(),
+            source_type: "Excel".to_string(),
+            connection_string: "/data/sales.xlsx".to_string(),
+            options: Json(HashMap::new()),
+            schema: None,
+            columns: None,
+            created_at: now,
+            updated_at: now,
+            status: "unknown".to_string(),
+            last_checked_at: None,
+            last_error: None,
+            secrets: None,
+        };
+
+        assert!(DataSourceConfig::try_from(row).is_err());
+    }
+}
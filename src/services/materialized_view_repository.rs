@@ -0,0 +1,149 @@
+use crate::datafusion_adapters::materialized_view::MaterializedView;
+use crate::utils::{AppError, AppResult};
+use sqlx::PgPool;
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct MaterializedViewRow {
+    pub id: String,
+    pub name: String,
+    pub sql: String,
+    pub schedule: Option<String>,
+    pub location: Option<String>,
+    pub last_refreshed_at: Option<chrono::NaiveDateTime>,
+    pub row_count: Option<i64>,
+    pub refresh_duration_ms: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl From<MaterializedViewRow> for MaterializedView {
+    fn from(row: MaterializedViewRow) -> Self {
+        MaterializedView {
+            id: row.id,
+            name: row.name,
+            sql: row.sql,
+            schedule: row.schedule,
+            location: row.location,
+            last_refreshed_at: row.last_refreshed_at,
+            row_count: row.row_count.map(|count| count as u64),
+            refresh_duration_ms: row.refresh_duration_ms.map(|ms| ms as u64),
+            last_error: row.last_error,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+pub struct MaterializedViewRepository {
+    pool: PgPool,
+}
+
+impl MaterializedViewRepository {
+    pub fn new(pool: PgPool) -> Self {
+        MaterializedViewRepository { pool }
+    }
+
+    pub async fn insert(&self, view: &MaterializedView) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO materialized_views (
+                id, name, sql, schedule, location, last_refreshed_at, row_count,
+                refresh_duration_ms, last_error, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(&view.id)
+        .bind(&view.name)
+        .bind(&view.sql)
+        .bind(&view.schedule)
+        .bind(&view.location)
+        .bind(view.last_refreshed_at)
+        .bind(view.row_count.map(|count| count as i64))
+        .bind(view.refresh_duration_ms.map(|ms| ms as i64))
+        .bind(&view.last_error)
+        .bind(view.created_at)
+        .bind(view.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(())
+    }
+
+    pub async fn update(&self, view: &MaterializedView) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE materialized_views
+            SET
+                name = $2,
+                sql = $3,
+                schedule = $4,
+                location = $5,
+                last_refreshed_at = $6,
+                row_count = $7,
+                refresh_duration_ms = $8,
+                last_error = $9,
+                updated_at = $10
+            WHERE id = $1
+            "#,
+        )
+        .bind(&view.id)
+        .bind(&view.name)
+        .bind(&view.sql)
+        .bind(&view.schedule)
+        .bind(&view.location)
+        .bind(view.last_refreshed_at)
+        .bind(view.row_count.map(|count| count as i64))
+        .bind(view.refresh_duration_ms.map(|ms| ms as i64))
+        .bind(&view.last_error)
+        .bind(view.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete(&self, id: &str) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM materialized_views
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list(&self) -> AppResult<Vec<MaterializedView>> {
+        let rows = sqlx::query_as::<_, MaterializedViewRow>(
+            r#"
+            SELECT
+                id,
+                name,
+                sql,
+                schedule,
+                location,
+                last_refreshed_at,
+                row_count,
+                refresh_duration_ms,
+                last_error,
+                created_at,
+                updated_at
+            FROM materialized_views
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(rows.into_iter().map(MaterializedView::from).collect())
+    }
+}
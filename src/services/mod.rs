@@ -0,0 +1,3 @@
+pub mod casbin_service;
+pub mod data_source_repository;
+pub mod materialized_view_repository;
@@ -1,3 +1,5 @@
+pub mod secrets;
+
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},